@@ -0,0 +1,95 @@
+use crate::matrix::Matrix;
+use std::fmt;
+use std::str::FromStr;
+
+/// The nonlinearity applied at the end of a network layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Activation {
+    #[default]
+    Sigmoid,
+    Relu,
+    Tanh,
+    Identity,
+}
+
+impl Activation {
+    pub fn apply(&self, m: &mut Matrix) {
+        match self {
+            Activation::Sigmoid => m.sigmoid(),
+            Activation::Relu => m.relu(),
+            Activation::Tanh => m.apply(f32::tanh),
+            Activation::Identity => {}
+        }
+    }
+
+    /// The derivative, written in terms of the activation's `output` since
+    /// that is what the network keeps around for backprop.
+    pub fn derivative(&self, output: &Matrix) -> Matrix {
+        match self {
+            Activation::Sigmoid => output.map(|a| a * (1.0 - a)),
+            Activation::Relu => output.map(|a| if a > 0.0 { 1.0 } else { 0.0 }),
+            Activation::Tanh => output.map(|a| 1.0 - a * a),
+            Activation::Identity => output.map(|_| 1.0),
+        }
+    }
+}
+
+impl fmt::Display for Activation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Activation::Sigmoid => "sigmoid",
+            Activation::Relu => "relu",
+            Activation::Tanh => "tanh",
+            Activation::Identity => "identity",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Activation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sigmoid" => Ok(Activation::Sigmoid),
+            "relu" => Ok(Activation::Relu),
+            "tanh" => Ok(Activation::Tanh),
+            "identity" => Ok(Activation::Identity),
+            _ => Err(format!("unknown activation `{s}`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::{assert_close, numeric_grad};
+
+    #[test]
+    fn test_derivative() {
+        let x = Matrix::from_iter(1, 4, vec![-1.5, -0.2, 0.3, 2.0]);
+        for activation in [Activation::Sigmoid, Activation::Relu, Activation::Tanh] {
+            let mut output = x.clone();
+            activation.apply(&mut output);
+            let expected = numeric_grad(&x, |x| {
+                let mut y = x.clone();
+                activation.apply(&mut y);
+                y.iter().sum()
+            });
+            assert_close(&activation.derivative(&output), &expected, 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        for activation in [
+            Activation::Sigmoid,
+            Activation::Relu,
+            Activation::Tanh,
+            Activation::Identity,
+        ] {
+            assert_eq!(activation.to_string().parse(), Ok(activation));
+        }
+        assert!("gelu".parse::<Activation>().is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+use std::fmt;
+
+/// A NaN or infinite value found by a trainer with anomaly detection on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Anomaly {
+    /// The step (counting from 0) whose forward or backward pass produced it.
+    pub step: u64,
+    pub layer: usize,
+    /// The parameter whose gradient holds the value, e.g. `weight` or
+    /// `norm.bias`, or `None` for the output activation of the layer.
+    pub parameter: Option<String>,
+    pub value: f32,
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Anomaly {
+            step, layer, value, ..
+        } = self;
+        match &self.parameter {
+            None => write!(
+                f,
+                "step {step}: layer {layer} produced a {value} activation"
+            ),
+            Some(parameter) => write!(
+                f,
+                "step {step}: the gradient of `layers.{layer}.{parameter}` contains {value}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Anomaly {}
+
+/// Finds the first non-finite value in the layer outputs of the last
+/// forward pass of `nn`, then in the parameter gradients held by `gradient`.
+pub(crate) fn check(
+    step: u64,
+    nn: &NeuralNetwork,
+    gradient: &NeuralNetwork,
+) -> Result<(), Anomaly> {
+    for (layer, output) in nn.outputs().iter().enumerate() {
+        if let Some(value) = non_finite(output) {
+            return Err(Anomaly {
+                step,
+                layer,
+                parameter: None,
+                value,
+            });
+        }
+    }
+    for (name, g) in gradient.parameters() {
+        if let Some(value) = non_finite(g) {
+            // `layers.{layer}.{parameter}`
+            let (layer, parameter) = name["layers.".len()..].split_once('.').unwrap();
+            return Err(Anomaly {
+                step,
+                layer: layer.parse().unwrap(),
+                parameter: Some(parameter.to_string()),
+                value,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn non_finite(values: &Matrix) -> Option<f32> {
+    values.iter().find(|x| !x.is_finite()).copied()
+}
@@ -0,0 +1,72 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rustml::{read_mnist_images, read_mnist_labels, Init, Matrix, NeuralNetwork};
+use std::path::Path;
+
+// Usage: mnist [DIR] [EPOCHS]
+//
+// DIR must contain the uncompressed MNIST files (`train-images-idx3-ubyte`,
+// `train-labels-idx1-ubyte`, `t10k-images-idx3-ubyte`, `t10k-labels-idx1-ubyte`).
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let dir = args.next().unwrap_or_else(|| "data/mnist".to_string());
+    let epochs = args
+        .next()
+        .map_or(5, |e| e.parse().expect("EPOCHS must be a number"));
+    let dir = Path::new(&dir);
+
+    let load = |images: &str, labels: &str| {
+        let x = read_mnist_images(dir.join(images)).unwrap_or_else(|e| panic!("{images}: {e}"));
+        let y = read_mnist_labels(dir.join(labels)).unwrap_or_else(|e| panic!("{labels}: {e}"));
+        (x, y)
+    };
+    let (train_input, train_output) = load("train-images-idx3-ubyte", "train-labels-idx1-ubyte");
+    let (test_input, test_output) = load("t10k-images-idx3-ubyte", "t10k-labels-idx1-ubyte");
+    println!(
+        "train = {} images, test = {} images",
+        train_input.rows(),
+        test_input.rows()
+    );
+
+    let batch_size = 32;
+    let rate = 1.0;
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut nn = NeuralNetwork::with_init(&[784, 64, 10], Init::XavierUniform, Init::Zeros, 1);
+    let mut gradient = nn.clone();
+
+    for epoch in 0..epochs {
+        let mut input = train_input.clone();
+        let permutation = input.shuffle_rows(&mut rng);
+        let output = train_output.select_rows(&permutation);
+
+        for start in (0..input.rows()).step_by(batch_size) {
+            let end = (start + batch_size).min(input.rows());
+            let x = input.slice_rows(start, end);
+            let y = output.slice_rows(start, end);
+            nn.backprop(&mut gradient, &x, &y);
+            nn.learn(&mut gradient, &rate);
+        }
+
+        println!(
+            "epoch: {epoch} cost: {:.6} test accuracy: {:.4}",
+            nn.cost(&test_input, &test_output),
+            accuracy(&mut nn, &test_input, &test_output)
+        );
+    }
+
+    println!(
+        "test accuracy: {:.4}",
+        accuracy(&mut nn, &test_input, &test_output)
+    );
+}
+
+fn accuracy(nn: &mut NeuralNetwork, input: &Matrix, output: &Matrix) -> f32 {
+    let predicted = nn.test(input).argmax_rows();
+    let correct = predicted
+        .iter()
+        .zip(output.argmax_rows())
+        .filter(|(a, b)| **a == *b)
+        .count();
+    correct as f32 / input.rows() as f32
+}
@@ -0,0 +1,285 @@
+use rustml::{
+    accuracy, Activation, Column, Config, CsvReader, Init, Logger, Loss, Matrix, NeuralNetwork,
+    Optimizer, RegressionReport, Trainer,
+};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+usage:
+  rustml train DATA --model MODEL [--config CONFIG] [--hidden 8,8]
+               [--activation sigmoid] [--output-activation sigmoid]
+               [--loss mse|cross_entropy|softmax_cross_entropy]
+               [--optimizer sgd|momentum|adam]
+               [--rate R] [--batch-size N] [--epochs N] [--seed N]
+               [--targets COLUMNS]
+  rustml eval DATA --model MODEL [--targets COLUMNS]
+  rustml predict DATA --model MODEL [--features COLUMNS] [--output FILE]
+
+DATA is a CSV file with a header line (pass --no-header otherwise). The
+targets default to the last column and the features to the others. MODEL
+is a safetensors file, or ONNX if it ends in `.onnx`. CONFIG is a JSON
+file with the sizes, activations, loss, optimizer, batch size, epochs and
+seed, replacing the corresponding options.";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = Args::parse(&args).and_then(|args| match args.command.as_str() {
+        "train" => train(&args),
+        "eval" => eval(&args),
+        "predict" => predict(&args),
+        command => Err(format!("unknown command `{command}`")),
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// `COMMAND DATA` followed by `--name value` options and `--no-header`.
+struct Args {
+    command: String,
+    data: String,
+    header: bool,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let [command, data, rest @ ..] = args else {
+            return Err("expected a command and a data file".to_string());
+        };
+        let mut parsed = Args {
+            command: command.clone(),
+            data: data.clone(),
+            header: true,
+            options: Vec::new(),
+        };
+        let mut rest = rest.iter();
+        while let Some(arg) = rest.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument `{arg}`"))?;
+            if name == "no-header" {
+                parsed.header = false;
+                continue;
+            }
+            let value = rest
+                .next()
+                .ok_or_else(|| format!("missing value for `--{name}`"))?;
+            parsed.options.push((name.to_string(), value.clone()));
+        }
+        Ok(parsed)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        let value = self.options.iter().rev().find(|(n, _)| n == name);
+        value.map(|(_, value)| value.as_str())
+    }
+
+    fn require(&self, name: &str) -> Result<&str, String> {
+        self.get(name).ok_or_else(|| format!("missing `--{name}`"))
+    }
+
+    fn parse_or<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.get(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("invalid `--{name}` value `{value}`")),
+            None => Ok(default),
+        }
+    }
+
+    fn reader(&self) -> CsvReader {
+        let reader = CsvReader::new().with_header(self.header);
+        match self.get("targets") {
+            Some(targets) => reader.with_targets(columns(targets)),
+            None => reader,
+        }
+    }
+}
+
+/// Comma separated column indices or header names.
+fn columns(list: &str) -> Vec<Column> {
+    list.split(',')
+        .map(|c| match c.trim().parse::<usize>() {
+            Ok(index) => Column::Index(index),
+            Err(_) => Column::Name(c.trim().to_string()),
+        })
+        .collect()
+}
+
+fn train(args: &Args) -> Result<(), String> {
+    let path = args.require("model")?;
+    let (input, output) = args
+        .reader()
+        .read(&args.data)
+        .map_err(|e| format!("{}: {e}", args.data))?;
+
+    let (mut nn, trainer, epochs) = match args.get("config") {
+        Some(config) => {
+            let config = Config::read(config).map_err(|e| format!("{config}: {e}"))?;
+            let (inputs, outputs) = (config.sizes[0], *config.sizes.last().unwrap());
+            if (inputs, outputs) != (input.cols(), output.cols()) {
+                return Err(format!(
+                    "the config has {inputs} inputs and {outputs} outputs but the data has {} \
+                     features and {} targets",
+                    input.cols(),
+                    output.cols()
+                ));
+            }
+            (config.network(), config.trainer(), config.epochs)
+        }
+        None => from_options(args, &input, &output)?,
+    };
+
+    let mut trainer = trainer.with_anomaly_detection(true);
+    let mut logger = Logger::new((epochs / 10).max(1));
+    trainer
+        .fit(&mut nn, &input, &output, epochs, &mut [&mut logger])
+        .map_err(|anomaly| format!("training diverged at {anomaly}"))?;
+
+    let written = match path.ends_with(".onnx") {
+        true => nn.write_onnx(path).map_err(|e| e.to_string()),
+        false => nn.write_safetensors(path).map_err(|e| e.to_string()),
+    };
+    written.map_err(|e| format!("{path}: {e}"))?;
+    println!("saved {path}");
+    Ok(())
+}
+
+/// The network, trainer and number of epochs given by the command line.
+fn from_options(
+    args: &Args,
+    input: &Matrix,
+    output: &Matrix,
+) -> Result<(NeuralNetwork, Trainer, usize), String> {
+    let mut sizes = vec![input.cols()];
+    if let Some(hidden) = args.get("hidden").filter(|h| !h.is_empty()) {
+        for size in hidden.split(',') {
+            let size = size
+                .trim()
+                .parse()
+                .ok()
+                .filter(|&size| size > 0)
+                .ok_or_else(|| format!("invalid `--hidden` size `{size}`"))?;
+            sizes.push(size);
+        }
+    }
+    sizes.push(output.cols());
+
+    let seed = args.parse_or("seed", 0)?;
+    let activation: Activation = args.parse_or("activation", Activation::Sigmoid)?;
+    let mut nn = NeuralNetwork::with_init(&sizes, Init::XavierUniform, Init::Zeros, seed);
+    for layer in 0..sizes.len() - 2 {
+        nn.set_activation(layer, activation);
+    }
+    nn.set_activation(
+        sizes.len() - 2,
+        args.parse_or("output-activation", Activation::Sigmoid)?,
+    );
+    nn.set_loss(args.parse_or("loss", Loss::Mse)?);
+
+    let rate = args.parse_or("rate", 0.01)?;
+    let optimizer = match args.get("optimizer").unwrap_or("adam") {
+        "sgd" => Optimizer::sgd(rate),
+        "momentum" => Optimizer::momentum(rate, 0.9),
+        "adam" => Optimizer::adam(rate),
+        other => return Err(format!("unknown optimizer `{other}`")),
+    };
+    let batch_size = args.parse_or("batch-size", 32)?;
+    if batch_size == 0 {
+        return Err("`--batch-size` must be positive".to_string());
+    }
+    let epochs = args.parse_or("epochs", 100)?;
+
+    let trainer = Trainer::new(optimizer, batch_size, seed);
+    Ok((nn, trainer, epochs))
+}
+
+fn eval(args: &Args) -> Result<(), String> {
+    let mut nn = load(args.require("model")?)?;
+    let (input, output) = args
+        .reader()
+        .read(&args.data)
+        .map_err(|e| format!("{}: {e}", args.data))?;
+    check_inputs(&nn, &input)?;
+    if output.cols() != *nn.sizes().last().unwrap() {
+        return Err(format!(
+            "the data has {} targets but the model has {} outputs",
+            output.cols(),
+            nn.sizes().last().unwrap()
+        ));
+    }
+
+    nn.eval();
+    println!("{}: {:.6}", nn.loss(), nn.cost(&input, &output));
+    let predicted = nn.test(&input);
+    match output.iter().all(|&y| y == 0.0 || y == 1.0) {
+        true => println!("accuracy: {:.4}", accuracy(&predicted, &output)),
+        false => println!("{}", RegressionReport::new(&predicted, &output)),
+    }
+    Ok(())
+}
+
+fn predict(args: &Args) -> Result<(), String> {
+    let mut nn = load(args.require("model")?)?;
+    let inputs = nn.sizes()[0];
+    let features = match args.get("features") {
+        Some(features) => columns(features),
+        None => (0..inputs).map(Column::Index).collect(),
+    };
+    // The reader always produces targets; they are not used here.
+    let reader = CsvReader::new()
+        .with_header(args.header)
+        .with_targets([features[0].clone()])
+        .with_features(features);
+    let (input, _) = reader
+        .read(&args.data)
+        .map_err(|e| format!("{}: {e}", args.data))?;
+    check_inputs(&nn, &input)?;
+
+    let output = nn.test(&input);
+    let mut csv = String::new();
+    if args.header {
+        let names = (0..output.cols()).map(|i| format!("output_{i}"));
+        csv += &names.collect::<Vec<_>>().join(",");
+        csv.push('\n');
+    }
+    for row in 0..output.rows() {
+        let values = output.get_row(row).unwrap().map(|x| x.to_string());
+        csv += &values.collect::<Vec<_>>().join(",");
+        csv.push('\n');
+    }
+
+    match args.get("output") {
+        Some(path) => std::fs::write(path, csv).map_err(|e| format!("{path}: {e}")),
+        None => {
+            print!("{csv}");
+            Ok(())
+        }
+    }
+}
+
+fn load(path: &str) -> Result<NeuralNetwork, String> {
+    let nn = match path.ends_with(".onnx") {
+        true => NeuralNetwork::read_onnx(path).map_err(|e| e.to_string()),
+        false => std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| NeuralNetwork::from_safetensors(&bytes).map_err(|e| e.to_string())),
+    };
+    nn.map_err(|e| format!("{path}: {e}"))
+}
+
+fn check_inputs(nn: &NeuralNetwork, input: &Matrix) -> Result<(), String> {
+    match input.cols() == nn.sizes()[0] {
+        true => Ok(()),
+        false => Err(format!(
+            "the data has {} features but the model has {} inputs",
+            input.cols(),
+            nn.sizes()[0]
+        )),
+    }
+}
@@ -0,0 +1,193 @@
+use rustml::{Json, Matrix, NeuralNetwork, Workspace};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::sync_channel;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Larger request bodies are refused with 413 before anything is allocated.
+const MAX_BODY: usize = 16 << 20;
+/// Longer request and header lines end the connection.
+const MAX_LINE: u64 = 8 << 10;
+/// How long a client may stall while sending or receiving.
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Connections are handled by this many threads; further ones wait in the
+/// listen backlog until a worker is free.
+const WORKERS: usize = 16;
+
+// Usage: serve MODEL [ADDR]
+//
+// Serves MODEL (`.onnx`, anything else is read as safetensors) on ADDR,
+// `127.0.0.1:8080` by default:
+//
+//   GET  /health    {"status":"ok"}
+//   GET  /metadata  {"sizes":[...],"activations":[...]}
+//   POST /predict   {"rows":[[...],...]} -> {"rows":[[...],...]}
+//
+// Bodies are limited to 16 MiB and at most 16 connections are handled at
+// once.
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().expect("usage: serve MODEL [ADDR]");
+    let addr = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+
+    let nn = match path.ends_with(".onnx") {
+        true => NeuralNetwork::read_onnx(&path).unwrap_or_else(|e| panic!("{path}: {e}")),
+        false => std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| NeuralNetwork::from_safetensors(&bytes).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| panic!("{path}: {e}")),
+    };
+    let nn = Arc::new(nn);
+
+    let listener = TcpListener::bind(&addr).unwrap_or_else(|e| panic!("{addr}: {e}"));
+    println!("listening on {}", listener.local_addr().unwrap());
+
+    let (sender, receiver) = sync_channel::<TcpStream>(0);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..WORKERS {
+        let (nn, receiver) = (Arc::clone(&nn), Arc::clone(&receiver));
+        std::thread::spawn(move || loop {
+            let Ok(stream) = receiver.lock().unwrap().recv() else {
+                return;
+            };
+            if let Err(e) = handle(&nn, stream) {
+                eprintln!("{e}");
+            }
+        });
+    }
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        sender.send(stream).unwrap();
+    }
+}
+
+fn handle(nn: &NeuralNetwork, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let line = read_line(&mut reader)?;
+    let mut parts = line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut length = 0;
+    loop {
+        let header = read_line(&mut reader)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    if length > MAX_BODY {
+        let error = Json::from(format!("body is larger than {MAX_BODY} bytes"));
+        let body = object(vec![("error", error)]);
+        return respond(stream, 413, &body.to_string());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    let (status, body) = match (method, path) {
+        ("GET", "/health") => (200, object(vec![("status", Json::from("ok"))])),
+        ("GET", "/metadata") => (200, metadata(nn)),
+        ("POST", "/predict") => match predict(nn, &body) {
+            Ok(rows) => (200, object(vec![("rows", rows)])),
+            Err(e) => (400, object(vec![("error", Json::from(e))])),
+        },
+        (_, "/health" | "/metadata" | "/predict") => (
+            405,
+            object(vec![("error", Json::from("method not allowed"))]),
+        ),
+        _ => (404, object(vec![("error", Json::from("not found"))])),
+    };
+    respond(stream, status, &body.to_string())
+}
+
+/// Reads a line of at most `MAX_LINE` bytes, or an empty string at the end
+/// of the stream.
+fn read_line(reader: &mut BufReader<TcpStream>) -> std::io::Result<String> {
+    let mut line = String::new();
+    reader.take(MAX_LINE).read_line(&mut line)?;
+    if line.len() as u64 == MAX_LINE && !line.ends_with('\n') {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "request line too long",
+        ));
+    }
+    Ok(line)
+}
+
+fn metadata(nn: &NeuralNetwork) -> Json {
+    let activations = nn.activations().iter().map(|a| a.to_string());
+    object(vec![
+        ("sizes", Json::from(nn.sizes())),
+        ("activations", Json::from(activations.collect::<Vec<_>>())),
+    ])
+}
+
+/// Runs the rows of a `{"rows": [[...], ...]}` body through the network.
+fn predict(nn: &NeuralNetwork, body: &[u8]) -> Result<Json, String> {
+    let body = std::str::from_utf8(body).map_err(|_| "body is not UTF-8".to_string())?;
+    let json = Json::parse(body).map_err(|e| e.to_string())?;
+    let rows = json
+        .get("rows")
+        .and_then(Json::as_array)
+        .ok_or("expected an object with a `rows` array")?;
+
+    let inputs = nn.sizes()[0];
+    let mut data = Vec::with_capacity(rows.len() * inputs);
+    for (i, row) in rows.iter().enumerate() {
+        let row = row
+            .as_array()
+            .ok_or_else(|| format!("row {i} is not an array"))?;
+        if row.len() != inputs {
+            return Err(format!(
+                "row {i} has {} values, expected {inputs}",
+                row.len()
+            ));
+        }
+        for x in row {
+            let x = x
+                .as_f64()
+                .ok_or_else(|| format!("row {i} contains a non-number"))?;
+            data.push(x as f32);
+        }
+    }
+
+    let input = Matrix::from_iter(rows.len(), inputs, data);
+    let mut workspace = Workspace::new();
+    let output = nn.predict(&input, &mut workspace);
+    let rows = (0..output.rows())
+        .map(|row| Json::from(output.get_row(row).unwrap().copied().collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    Ok(Json::Array(rows))
+}
+
+fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(
+        fields
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+fn respond(mut stream: TcpStream, status: u16, body: &str) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        413 => "Content Too Large",
+        _ => "Method Not Allowed",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
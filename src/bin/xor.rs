@@ -1,8 +1,14 @@
-use rand::prelude::*;
-use rand::rngs::StdRng;
-use rustml::{Matrix, NeuralNetwork};
+use rustml::{Callback, EarlyStopping, Init, Logger, Matrix, NeuralNetwork, Optimizer, Trainer};
+use std::ops::ControlFlow;
+use std::path::Path;
 
+// Usage: xor [CHECKPOINT]
+//
+// With CHECKPOINT, training resumes from that file if it exists and the
+// state is saved to it every 1000 epochs.
 fn main() {
+    let checkpoint = std::env::args().nth(1);
+
     let input = Matrix::from_iter(
         4,
         2,
@@ -18,30 +24,36 @@ fn main() {
     println!("input = \n{input}");
     println!("output = \n{output}");
 
-    let eps = 1e-1;
-    let rate = 1e-1;
-
-    let rng = StdRng::seed_from_u64(1);
-    // let rng = rand::thread_rng();
-
-    let mut nn = NeuralNetwork::from_iter(
-        &[2, 2, 1],
-        std::iter::repeat_with(|| rng.clone().gen_range(0.0..1.0)),
-    );
+    let epochs = 100_000;
 
-    let mut gradient = NeuralNetwork::new(&[2, 2, 1]);
+    let mut nn = NeuralNetwork::with_init(&[2, 2, 1], Init::XavierUniform, Init::Zeros, 1);
+    let mut trainer = match &checkpoint {
+        Some(path) if Path::new(path).exists() => {
+            let trainer =
+                Trainer::read_checkpoint(&mut nn, path).unwrap_or_else(|e| panic!("{path}: {e}"));
+            println!("resuming from {path} at epoch {}", trainer.epoch());
+            trainer
+        }
+        _ => Trainer::new(Optimizer::sgd(1.0), 4, 1),
+    };
 
     println!("nn = {nn}");
     println!("cost = {:.32}", nn.cost(&input, &output));
 
-    let mut count = 0;
-    for i in 0..100_000 {
-        nn.finite_diff(&mut gradient, &eps, &input, &output);
-        nn.learn(&mut gradient, &rate);
-        count += 1;
-        if count % 100 == 0 {
-            println!("i: {i} cost: {:.32}", nn.cost(&input, &output));
-        }
+    // XOR has no held-out data, so early stopping watches the training set
+    // and ends once the cost stops improving.
+    let mut early = EarlyStopping::new(input.clone(), output.clone(), 500).with_min_delta(1e-5);
+    let mut logger = Logger::new(100);
+    let mut saver = checkpoint.map(Checkpoint);
+    let mut callbacks: Vec<&mut dyn Callback> = vec![&mut early, &mut logger];
+    if let Some(saver) = &mut saver {
+        callbacks.push(saver);
+    }
+    trainer
+        .fit(&mut nn, &input, &output, epochs, &mut callbacks)
+        .unwrap_or_else(|anomaly| panic!("{anomaly}"));
+    if let Some(epoch) = early.stopped_epoch() {
+        println!("stopped early after epoch {epoch}");
     }
 
     println!("nn after training = {nn}");
@@ -57,3 +69,23 @@ fn main() {
         );
     }
 }
+
+/// Saves the training state to the file every 1000 epochs.
+struct Checkpoint(String);
+
+impl Callback for Checkpoint {
+    fn on_epoch_end(
+        &mut self,
+        trainer: &Trainer,
+        nn: &mut NeuralNetwork,
+        _cost: f32,
+    ) -> ControlFlow<()> {
+        if trainer.epoch().is_multiple_of(1000) {
+            let path = &self.0;
+            trainer
+                .write_checkpoint(nn, path)
+                .unwrap_or_else(|e| panic!("{path}: {e}"));
+        }
+        ControlFlow::Continue(())
+    }
+}
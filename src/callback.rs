@@ -0,0 +1,252 @@
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+use crate::trainer::Trainer;
+use std::ops::ControlFlow;
+
+/// Hooks called by [`Trainer::fit`]. Returning `ControlFlow::Break` from
+/// `on_batch_end` or `on_epoch_end` stops training after that batch or
+/// epoch; `on_train_end` is called either way.
+pub trait Callback {
+    /// Called after every step with the cost of its batch.
+    fn on_batch_end(
+        &mut self,
+        _trainer: &Trainer,
+        _nn: &mut NeuralNetwork,
+        _cost: f32,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called after every epoch with the mean cost of its batches.
+    fn on_epoch_end(
+        &mut self,
+        _trainer: &Trainer,
+        _nn: &mut NeuralNetwork,
+        _cost: f32,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_train_end(&mut self, _trainer: &Trainer, _nn: &mut NeuralNetwork) {}
+}
+
+/// Stops training once the cost on a validation set has not improved by more
+/// than `min_delta` for `patience` epochs, and by default restores the
+/// weights of the best epoch when training ends.
+#[derive(Clone, Debug)]
+pub struct EarlyStopping {
+    input: Matrix,
+    target: Matrix,
+    patience: usize,
+    min_delta: f32,
+    restore_best: bool,
+    best: Option<(usize, f32)>,
+    weights: Option<NeuralNetwork>,
+    wait: usize,
+    stopped: Option<usize>,
+}
+
+impl EarlyStopping {
+    pub fn new(input: Matrix, target: Matrix, patience: usize) -> Self {
+        assert_eq!(input.rows(), target.rows());
+        Self {
+            input,
+            target,
+            patience,
+            min_delta: 0.0,
+            restore_best: true,
+            best: None,
+            weights: None,
+            wait: 0,
+            stopped: None,
+        }
+    }
+
+    pub fn with_min_delta(mut self, min_delta: f32) -> Self {
+        assert!(min_delta >= 0.0);
+        self.min_delta = min_delta;
+        self
+    }
+
+    pub fn with_restore_best(mut self, restore_best: bool) -> Self {
+        self.restore_best = restore_best;
+        self
+    }
+
+    /// The epoch with the lowest validation cost and that cost.
+    pub fn best(&self) -> Option<(usize, f32)> {
+        self.best
+    }
+
+    /// The epoch training was stopped after, if it was.
+    pub fn stopped_epoch(&self) -> Option<usize> {
+        self.stopped
+    }
+}
+
+impl Callback for EarlyStopping {
+    fn on_epoch_end(
+        &mut self,
+        trainer: &Trainer,
+        nn: &mut NeuralNetwork,
+        _cost: f32,
+    ) -> ControlFlow<()> {
+        let cost = nn.loss().cost(&nn.test(&self.input), &self.target);
+        match &self.best {
+            Some((_, best)) if cost >= best - self.min_delta => {
+                self.wait += 1;
+                if self.wait >= self.patience {
+                    self.stopped = Some(trainer.epoch());
+                    return ControlFlow::Break(());
+                }
+            }
+            _ => {
+                self.best = Some((trainer.epoch(), cost));
+                if self.restore_best {
+                    self.weights = Some(nn.clone());
+                }
+                self.wait = 0;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn on_train_end(&mut self, _trainer: &Trainer, nn: &mut NeuralNetwork) {
+        if let Some(weights) = &self.weights {
+            *nn = weights.clone();
+        }
+    }
+}
+
+/// Prints `epoch: N cost: C` with the mean training cost every `every`
+/// epochs and after the last one.
+#[derive(Clone, Debug)]
+pub struct Logger {
+    every: usize,
+    last: Option<(usize, f32)>,
+}
+
+impl Logger {
+    pub fn new(every: usize) -> Self {
+        assert!(every > 0);
+        Self { every, last: None }
+    }
+}
+
+impl Callback for Logger {
+    fn on_epoch_end(
+        &mut self,
+        trainer: &Trainer,
+        _nn: &mut NeuralNetwork,
+        cost: f32,
+    ) -> ControlFlow<()> {
+        let epoch = trainer.epoch();
+        self.last = Some((epoch, cost));
+        if epoch.is_multiple_of(self.every) {
+            println!("epoch: {epoch} cost: {cost:.6}");
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn on_train_end(&mut self, _trainer: &Trainer, _nn: &mut NeuralNetwork) {
+        if let Some((epoch, cost)) = self.last.take() {
+            if !epoch.is_multiple_of(self.every) {
+                println!("epoch: {epoch} cost: {cost:.6}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::Init;
+    use crate::optimizer::Optimizer;
+
+    #[derive(Default)]
+    struct Counter {
+        batches: usize,
+        epochs: Vec<usize>,
+        ended: bool,
+    }
+
+    impl Callback for Counter {
+        fn on_batch_end(&mut self, _: &Trainer, _: &mut NeuralNetwork, _: f32) -> ControlFlow<()> {
+            self.batches += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_epoch_end(&mut self, t: &Trainer, _: &mut NeuralNetwork, _: f32) -> ControlFlow<()> {
+            self.epochs.push(t.epoch());
+            ControlFlow::Continue(())
+        }
+
+        fn on_train_end(&mut self, _: &Trainer, _: &mut NeuralNetwork) {
+            self.ended = true;
+        }
+    }
+
+    fn data() -> (Matrix, Matrix, NeuralNetwork) {
+        let input = Matrix::from_iter(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let output = Matrix::from_iter(4, 1, vec![0.0, 1.0, 1.0, 1.0]);
+        let nn = NeuralNetwork::with_init(&[2, 1], Init::XavierUniform, Init::Zeros, 3);
+        (input, output, nn)
+    }
+
+    #[test]
+    fn test_hooks() {
+        let (input, output, mut nn) = data();
+        let mut trainer = Trainer::new(Optimizer::sgd(0.5), 3, 0);
+        let mut counter = Counter::default();
+        trainer
+            .fit(&mut nn, &input, &output, 3, &mut [&mut counter])
+            .unwrap();
+        assert_eq!(counter.batches, 6);
+        assert_eq!(counter.epochs, vec![1, 2, 3]);
+        assert!(counter.ended);
+
+        // A finished trainer only calls `on_train_end`.
+        let mut counter = Counter::default();
+        trainer
+            .fit(&mut nn, &input, &output, 3, &mut [&mut counter])
+            .unwrap();
+        assert_eq!((counter.batches, counter.epochs.len()), (0, 0));
+        assert!(counter.ended);
+    }
+
+    #[test]
+    fn test_early_stopping() {
+        // Validation targets opposite to the training ones get worse with
+        // every epoch, so the first epoch stays the best.
+        let (input, output, mut nn) = data();
+        let validation = output.map(|y| 1.0 - y);
+        let mut early = EarlyStopping::new(input.clone(), validation.clone(), 3);
+        let mut counter = Counter::default();
+        let mut trainer = Trainer::new(Optimizer::sgd(0.5), 4, 0);
+        trainer
+            .fit(
+                &mut nn,
+                &input,
+                &output,
+                100,
+                &mut [&mut early, &mut counter],
+            )
+            .unwrap();
+
+        assert_eq!(early.stopped_epoch(), Some(4));
+        assert_eq!(trainer.epoch(), 4);
+        assert_eq!(counter.epochs, vec![1, 2, 3, 4]);
+        let (epoch, best) = early.best().unwrap();
+        assert_eq!(epoch, 1);
+        assert_eq!(nn.cost(&input, &validation), best);
+
+        let (input, output, mut nn) = data();
+        let mut early =
+            EarlyStopping::new(input.clone(), validation.clone(), 3).with_restore_best(false);
+        let mut trainer = Trainer::new(Optimizer::sgd(0.5), 4, 0);
+        trainer
+            .fit(&mut nn, &input, &output, 100, &mut [&mut early])
+            .unwrap();
+        assert!(nn.cost(&input, &validation) > early.best().unwrap().1);
+    }
+}
@@ -0,0 +1,637 @@
+use crate::clip::Clip;
+use crate::json::Json;
+use crate::neural_network::NeuralNetwork;
+use crate::optimizer::{Method, Optimizer};
+use crate::regularizer::Regularizer;
+use crate::safetensors::{deserialize, restore, serialize, shape, store, SafetensorsError};
+use crate::scheduler::{Interval, Schedule, Scheduler};
+use crate::trainer::Trainer;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(std::io::Error),
+    Safetensors(SafetensorsError),
+    /// A training counter or optimizer setting is missing or invalid.
+    Metadata(String),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(err) => write!(f, "{err}"),
+            CheckpointError::Safetensors(err) => write!(f, "{err}"),
+            CheckpointError::Metadata(msg) => write!(f, "invalid checkpoint: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<std::io::Error> for CheckpointError {
+    fn from(err: std::io::Error) -> Self {
+        CheckpointError::Io(err)
+    }
+}
+
+impl From<SafetensorsError> for CheckpointError {
+    fn from(err: SafetensorsError) -> Self {
+        CheckpointError::Safetensors(err)
+    }
+}
+
+const FIRST: &str = "optimizer.first.";
+const SECOND: &str = "optimizer.second.";
+
+impl Trainer {
+    /// Serializes `nn` together with the training state: the optimizer
+    /// settings and moment buffers, the scheduler, gradient clipping, and the
+    /// epoch, batch and step counters. The activations, loss and weight
+    /// regularization of `nn` are recorded to be checked on resume.
+    ///
+    /// The file is a safetensors file holding the tensors of
+    /// [`NeuralNetwork::to_safetensors`] plus `optimizer.first.{name}` and
+    /// `optimizer.second.{name}` for the moments of every parameter, with the
+    /// rest stored as metadata.
+    pub fn to_checkpoint(&self, nn: &NeuralNetwork) -> Vec<u8> {
+        let mut tensors = nn
+            .tensors()
+            .into_iter()
+            .map(|(name, tensor)| store(name, tensor))
+            .collect::<Vec<_>>();
+        let (first, second) = self.optimizer.moments();
+        for (prefix, moments) in [(FIRST, first), (SECOND, second)] {
+            for ((name, _), moment) in nn.parameters().into_iter().zip(moments) {
+                tensors.push(store(format!("{prefix}{name}"), moment));
+            }
+        }
+
+        let activations = nn.activations().iter().map(|a| a.to_string());
+        let mut metadata = vec![
+            ("format", "rustml".to_string()),
+            ("sizes", Json::from(nn.sizes()).to_string()),
+            (
+                "activations",
+                Json::from(activations.collect::<Vec<_>>()).to_string(),
+            ),
+            ("loss", nn.loss().to_string()),
+            ("regularization", regularization(nn)),
+            ("batch_size", self.batch_size.to_string()),
+            ("seed", self.seed.to_string()),
+            ("epoch", self.epoch.to_string()),
+            ("batch", self.batch.to_string()),
+            ("rows", self.order.len().to_string()),
+            ("step", self.step.to_string()),
+            ("optimizer.rate", self.optimizer.rate().to_string()),
+            ("optimizer.updates", self.optimizer.updates().to_string()),
+        ];
+        match self.optimizer.method() {
+            Method::Sgd => metadata.push(("optimizer", "sgd".to_string())),
+            Method::Momentum { momentum } => {
+                metadata.push(("optimizer", "momentum".to_string()));
+                metadata.push(("optimizer.momentum", momentum.to_string()));
+            }
+            Method::Adam { beta1, beta2, eps } => {
+                metadata.push(("optimizer", "adam".to_string()));
+                metadata.push(("optimizer.beta1", beta1.to_string()));
+                metadata.push(("optimizer.beta2", beta2.to_string()));
+                metadata.push(("optimizer.eps", eps.to_string()));
+            }
+        }
+        if let Some(scheduler) = &self.scheduler {
+            metadata.extend(scheduler_metadata(scheduler));
+        }
+        match self.clip {
+            None => {}
+            Some(Clip::Norm(max)) => {
+                metadata.push(("clip", "norm".to_string()));
+                metadata.push(("clip.max", max.to_string()));
+            }
+            Some(Clip::Value(max)) => {
+                metadata.push(("clip", "value".to_string()));
+                metadata.push(("clip.max", max.to_string()));
+            }
+        }
+        let metadata = metadata
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect::<Vec<_>>();
+        serialize(&tensors, &metadata)
+    }
+
+    pub fn write_checkpoint<P: AsRef<Path>>(
+        &self,
+        nn: &NeuralNetwork,
+        path: P,
+    ) -> Result<(), CheckpointError> {
+        Ok(std::fs::write(path, self.to_checkpoint(nn))?)
+    }
+
+    /// Restores the tensors of `nn` from a checkpoint and returns the trainer
+    /// to continue with. `nn` must have the architecture, activations, loss
+    /// and regularization the checkpoint was written from, so that training
+    /// continues on the same objective; on error it is left unchanged. A
+    /// checkpoint written mid-epoch must be resumed on data with the same
+    /// number of rows.
+    pub fn resume(nn: &mut NeuralNetwork, bytes: &[u8]) -> Result<Trainer, CheckpointError> {
+        let (tensors, metadata) = deserialize(bytes)?;
+        let activations = nn.activations().iter().map(|a| a.to_string());
+        let activations = Json::from(activations.collect::<Vec<_>>()).to_string();
+        for (key, expected) in [
+            ("sizes", Json::from(nn.sizes()).to_string()),
+            ("activations", activations),
+            ("loss", nn.loss().to_string()),
+            ("regularization", regularization(nn)),
+        ] {
+            let found = get(&metadata, key)?;
+            if found != expected {
+                return Err(CheckpointError::Metadata(format!(
+                    "{key} `{found}` doesn't match the network's `{expected}`"
+                )));
+            }
+        }
+
+        let method = match get(&metadata, "optimizer")? {
+            "sgd" => Method::Sgd,
+            "momentum" => Method::Momentum {
+                momentum: parse(&metadata, "optimizer.momentum")?,
+            },
+            "adam" => Method::Adam {
+                beta1: parse(&metadata, "optimizer.beta1")?,
+                beta2: parse(&metadata, "optimizer.beta2")?,
+                eps: parse(&metadata, "optimizer.eps")?,
+            },
+            other => {
+                return Err(CheckpointError::Metadata(format!(
+                    "unknown optimizer `{other}`"
+                )))
+            }
+        };
+        let mut optimizer = Optimizer::new(method, parse(&metadata, "optimizer.rate")?);
+        let updates = parse(&metadata, "optimizer.updates")?;
+        let batch_size = parse(&metadata, "batch_size")?;
+        if batch_size == 0 {
+            return Err(CheckpointError::Metadata("`batch_size` is 0".to_string()));
+        }
+
+        let (mut moments, tensors): (Vec<_>, Vec<_>) = tensors
+            .into_iter()
+            .partition(|(name, _, _)| name.starts_with("optimizer."));
+        let mut take = |prefix: &str, used: bool| {
+            let mut taken = Vec::new();
+            if !used || updates == 0 {
+                return Ok(taken);
+            }
+            for (name, parameter) in nn.parameters() {
+                let name = format!("{prefix}{name}");
+                let i = moments
+                    .iter()
+                    .position(|(n, _, _)| *n == name)
+                    .ok_or_else(|| SafetensorsError::Missing(name.clone()))?;
+                let (name, found, moment) = moments.swap_remove(i);
+                let expected = shape(&name, parameter);
+                if found != expected {
+                    return Err(SafetensorsError::Shape {
+                        name,
+                        expected,
+                        found,
+                    });
+                }
+                taken.push(restore(&name, moment));
+            }
+            Ok(taken)
+        };
+        let first = take(FIRST, method != Method::Sgd)?;
+        let second = take(SECOND, matches!(method, Method::Adam { .. }))?;
+        if let Some((name, _, _)) = moments.into_iter().next() {
+            return Err(SafetensorsError::Unexpected(name).into());
+        }
+        optimizer.set_state(updates, first, second);
+
+        let mut trainer = Trainer::new(optimizer, batch_size, parse(&metadata, "seed")?);
+        trainer.epoch = parse(&metadata, "epoch")?;
+        trainer.batch = parse(&metadata, "batch")?;
+        let rows: usize = parse(&metadata, "rows")?;
+        if trainer.batch > 0 && trainer.batch.saturating_mul(batch_size) >= rows {
+            return Err(CheckpointError::Metadata(format!(
+                "`batch` {} is past the end of an epoch of {rows} rows with `batch_size` {batch_size}",
+                trainer.batch
+            )));
+        }
+        trainer.step = parse(&metadata, "step")?;
+        if metadata.iter().any(|(key, _)| key == "scheduler") {
+            trainer.scheduler = Some(scheduler(&metadata)?);
+        }
+        if metadata.iter().any(|(key, _)| key == "clip") {
+            let max: f32 = parse(&metadata, "clip.max")?;
+            if max.is_nan() || max <= 0.0 {
+                return Err(CheckpointError::Metadata(format!(
+                    "`clip.max` {max} must be positive"
+                )));
+            }
+            trainer.clip = Some(match get(&metadata, "clip")? {
+                "norm" => Clip::Norm(max),
+                "value" => Clip::Value(max),
+                other => {
+                    return Err(CheckpointError::Metadata(format!(
+                        "unknown clipping `{other}`"
+                    )))
+                }
+            });
+        }
+
+        nn.load_tensors(tensors)?;
+        Ok(trainer)
+    }
+
+    pub fn read_checkpoint<P: AsRef<Path>>(
+        nn: &mut NeuralNetwork,
+        path: P,
+    ) -> Result<Trainer, CheckpointError> {
+        Self::resume(nn, &std::fs::read(path)?)
+    }
+}
+
+fn scheduler_metadata(scheduler: &Scheduler) -> Vec<(&'static str, String)> {
+    let interval = match scheduler.interval {
+        Interval::Step => "step",
+        Interval::Epoch => "epoch",
+    };
+    let mut metadata = vec![
+        ("scheduler", scheduler.schedule.name().to_string()),
+        ("scheduler.rate", scheduler.rate.to_string()),
+        ("scheduler.interval", interval.to_string()),
+        ("scheduler.warmup", scheduler.warmup.to_string()),
+    ];
+    match scheduler.schedule {
+        Schedule::Constant => {}
+        Schedule::StepDecay { step_size, gamma } => {
+            metadata.push(("scheduler.step_size", step_size.to_string()));
+            metadata.push(("scheduler.gamma", gamma.to_string()));
+        }
+        Schedule::Exponential { gamma } => {
+            metadata.push(("scheduler.gamma", gamma.to_string()));
+        }
+        Schedule::CosineWarmRestarts {
+            period,
+            multiplier,
+            min_rate,
+        } => {
+            metadata.push(("scheduler.period", period.to_string()));
+            metadata.push(("scheduler.multiplier", multiplier.to_string()));
+            metadata.push(("scheduler.min_rate", min_rate.to_string()));
+        }
+        Schedule::OneCycle {
+            total,
+            pct_start,
+            div_factor,
+            final_div_factor,
+        } => {
+            metadata.push(("scheduler.total", total.to_string()));
+            metadata.push(("scheduler.pct_start", pct_start.to_string()));
+            metadata.push(("scheduler.div_factor", div_factor.to_string()));
+            metadata.push(("scheduler.final_div_factor", final_div_factor.to_string()));
+        }
+        Schedule::ReduceOnPlateau {
+            factor,
+            patience,
+            min_delta,
+            min_rate,
+        } => {
+            metadata.push(("scheduler.factor", factor.to_string()));
+            metadata.push(("scheduler.patience", patience.to_string()));
+            metadata.push(("scheduler.min_delta", min_delta.to_string()));
+            metadata.push(("scheduler.min_rate", min_rate.to_string()));
+            metadata.push(("scheduler.best", scheduler.best.to_string()));
+            metadata.push(("scheduler.wait", scheduler.wait.to_string()));
+            metadata.push(("scheduler.scale", scheduler.scale.to_string()));
+        }
+    }
+    metadata
+}
+
+fn scheduler(metadata: &[(String, String)]) -> Result<Scheduler, CheckpointError> {
+    let schedule = match get(metadata, "scheduler")? {
+        "constant" => Schedule::Constant,
+        "step_decay" => Schedule::StepDecay {
+            step_size: parse(metadata, "scheduler.step_size")?,
+            gamma: parse(metadata, "scheduler.gamma")?,
+        },
+        "exponential" => Schedule::Exponential {
+            gamma: parse(metadata, "scheduler.gamma")?,
+        },
+        "cosine_warm_restarts" => Schedule::CosineWarmRestarts {
+            period: parse(metadata, "scheduler.period")?,
+            multiplier: parse(metadata, "scheduler.multiplier")?,
+            min_rate: parse(metadata, "scheduler.min_rate")?,
+        },
+        "one_cycle" => Schedule::OneCycle {
+            total: parse(metadata, "scheduler.total")?,
+            pct_start: parse(metadata, "scheduler.pct_start")?,
+            div_factor: parse(metadata, "scheduler.div_factor")?,
+            final_div_factor: parse(metadata, "scheduler.final_div_factor")?,
+        },
+        "reduce_on_plateau" => Schedule::ReduceOnPlateau {
+            factor: parse(metadata, "scheduler.factor")?,
+            patience: parse(metadata, "scheduler.patience")?,
+            min_delta: parse(metadata, "scheduler.min_delta")?,
+            min_rate: parse(metadata, "scheduler.min_rate")?,
+        },
+        other => {
+            return Err(CheckpointError::Metadata(format!(
+                "unknown scheduler `{other}`"
+            )))
+        }
+    };
+    let interval = match get(metadata, "scheduler.interval")? {
+        "step" => Interval::Step,
+        "epoch" => Interval::Epoch,
+        other => {
+            return Err(CheckpointError::Metadata(format!(
+                "unknown scheduler interval `{other}`"
+            )))
+        }
+    };
+    schedule
+        .check()
+        .map_err(|message| CheckpointError::Metadata(format!("scheduler {message}")))?;
+    let mut scheduler = Scheduler::new(parse(metadata, "scheduler.rate")?, schedule)
+        .with_interval(interval)
+        .with_warmup(parse(metadata, "scheduler.warmup")?);
+    if let Schedule::ReduceOnPlateau { .. } = schedule {
+        scheduler.best = parse(metadata, "scheduler.best")?;
+        scheduler.wait = parse(metadata, "scheduler.wait")?;
+        scheduler.scale = parse(metadata, "scheduler.scale")?;
+    }
+    Ok(scheduler)
+}
+
+/// The regularizer of every layer as in a [`crate::Config`], e.g.
+/// `[{"l1":0,"l2":0.001},null]`.
+fn regularization(nn: &NeuralNetwork) -> String {
+    let layers = nn
+        .regularizers()
+        .iter()
+        .map(|regularizer| match regularizer {
+            Some(Regularizer { l1, l2 }) => format!(r#"{{"l1":{l1},"l2":{l2}}}"#),
+            None => "null".to_string(),
+        });
+    format!("[{}]", layers.collect::<Vec<_>>().join(","))
+}
+
+fn get<'a>(metadata: &'a [(String, String)], key: &str) -> Result<&'a str, CheckpointError> {
+    metadata
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+        .ok_or_else(|| CheckpointError::Metadata(format!("missing `{key}`")))
+}
+
+fn parse<T: FromStr>(metadata: &[(String, String)], key: &str) -> Result<T, CheckpointError> {
+    let value = get(metadata, key)?;
+    value
+        .parse()
+        .map_err(|_| CheckpointError::Metadata(format!("invalid `{key}`: {value:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::Init;
+    use crate::layer::{BatchNorm1d, Norm};
+    use crate::loss::Loss;
+    use crate::matrix::Matrix;
+
+    fn network() -> NeuralNetwork {
+        let mut nn = NeuralNetwork::with_init(&[3, 5, 4, 2], Init::HeNormal, Init::Zeros, 11);
+        nn.set_dropout(1, 0.3, 0);
+        nn.set_norm(1, Norm::Batch(BatchNorm1d::new(4)));
+        nn
+    }
+
+    fn data() -> (Matrix, Matrix) {
+        let input = Matrix::from_iter(10, 3, (0..30).map(|x| (x as f32 * 0.37).sin()));
+        let output = Matrix::from_iter(10, 2, (0..20).map(|x| (x % 3 == 0) as u8 as f32));
+        (input, output)
+    }
+
+    #[test]
+    fn test_resume_matches_uninterrupted_run() {
+        let (input, output) = data();
+
+        let mut expected = network();
+        let mut trainer = Trainer::new(Optimizer::adam(0.01), 3, 5);
+        for _ in 0..9 {
+            trainer.train_step(&mut expected, &input, &output);
+        }
+
+        // Interrupted mid-epoch: 9 steps of 4 batches per epoch.
+        let mut nn = network();
+        let mut trainer = Trainer::new(Optimizer::adam(0.01), 3, 5);
+        for _ in 0..6 {
+            trainer.train_step(&mut nn, &input, &output);
+        }
+        let bytes = trainer.to_checkpoint(&nn);
+
+        let mut nn = network();
+        let mut trainer = Trainer::resume(&mut nn, &bytes).unwrap();
+        assert_eq!(
+            (trainer.epoch(), trainer.batch(), trainer.step()),
+            (1, 2, 6)
+        );
+        assert_eq!(trainer.optimizer().method(), Optimizer::adam(0.01).method());
+        for _ in 0..3 {
+            trainer.train_step(&mut nn, &input, &output);
+        }
+        assert_eq!(nn.tensors(), expected.tensors());
+    }
+
+    #[test]
+    fn test_round_trip_and_errors() {
+        let (input, output) = data();
+        let mut nn = network();
+        let mut trainer = Trainer::new(Optimizer::momentum(0.1, 0.9), 4, 1);
+        trainer.train_epoch(&mut nn, &input, &output);
+
+        let path = std::env::temp_dir().join("rustml_test_checkpoint.safetensors");
+        trainer.write_checkpoint(&nn, &path).unwrap();
+        let mut loaded = network();
+        let resumed = Trainer::read_checkpoint(&mut loaded, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.tensors(), nn.tensors());
+        assert_eq!(
+            resumed.optimizer().moments().0,
+            trainer.optimizer().moments().0
+        );
+        assert!(resumed.optimizer().moments().1.is_empty());
+
+        let bytes = trainer.to_checkpoint(&nn);
+        let err = Trainer::resume(&mut NeuralNetwork::new(&[3, 5, 4, 2]), &bytes).unwrap_err();
+        assert!(matches!(
+            err,
+            CheckpointError::Safetensors(SafetensorsError::Unexpected(_))
+        ));
+
+        let (tensors, mut metadata) = deserialize(&bytes).unwrap();
+        let tensors = tensors
+            .iter()
+            .map(|(name, shape, tensor)| (name.clone(), shape.clone(), tensor))
+            .collect::<Vec<_>>();
+        for (key, value) in &mut metadata {
+            if key == "optimizer" {
+                *value = "nesterov".to_string();
+            }
+        }
+        let bytes = serialize(&tensors, &metadata);
+        let err = Trainer::resume(&mut network(), &bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid checkpoint: unknown optimizer `nesterov`"
+        );
+    }
+
+    #[test]
+    fn test_resume_objective() {
+        let mut nn = network();
+        nn.set_loss(Loss::CrossEntropy);
+        nn.set_regularizer(0, Regularizer::l2(0.01));
+        let bytes = Trainer::new(Optimizer::sgd(0.1), 4, 1).to_checkpoint(&nn);
+        assert!(Trainer::resume(&mut nn.clone(), &bytes).is_ok());
+
+        let mut other = nn.clone();
+        other.set_loss(Loss::Mse);
+        let err = Trainer::resume(&mut other, &bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid checkpoint: loss `cross_entropy` doesn't match the network's `mse`"
+        );
+
+        let mut other = nn.clone();
+        other.set_regularizer(0, Regularizer::l1(0.01));
+        let err = Trainer::resume(&mut other, &bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid checkpoint: regularization `[{\"l1\":0,\"l2\":0.01},null,null]` \
+             doesn't match the network's `[{\"l1\":0.01,\"l2\":0},null,null]`"
+        );
+    }
+
+    #[test]
+    fn test_resume_with_scheduler() {
+        let (input, output) = data();
+        let scheduler = || {
+            Scheduler::new(
+                0.05,
+                Schedule::ReduceOnPlateau {
+                    factor: 0.5,
+                    patience: 0,
+                    min_delta: 0.0,
+                    min_rate: 0.0,
+                },
+            )
+            .with_warmup(3)
+        };
+        let trainer = || {
+            Trainer::new(Optimizer::adam(1.0), 3, 5)
+                .with_scheduler(scheduler())
+                .with_clipping(Clip::Norm(0.5))
+        };
+
+        let mut expected = network();
+        let mut uninterrupted = trainer();
+        for _ in 0..6 {
+            uninterrupted.train_epoch(&mut expected, &input, &output);
+        }
+
+        let mut nn = network();
+        let mut trainer = trainer();
+        for _ in 0..3 {
+            trainer.train_epoch(&mut nn, &input, &output);
+        }
+        let bytes = trainer.to_checkpoint(&nn);
+        let saved = trainer.scheduler().cloned();
+        assert_ne!(saved, Some(scheduler()));
+        let mut nn = network();
+        let mut trainer = Trainer::resume(&mut nn, &bytes).unwrap();
+        assert_eq!(trainer.scheduler().cloned(), saved);
+        assert_eq!(trainer.clip(), Some(Clip::Norm(0.5)));
+        for _ in 0..3 {
+            trainer.train_epoch(&mut nn, &input, &output);
+        }
+        assert_eq!(trainer.scheduler(), uninterrupted.scheduler());
+        assert_eq!(trainer.optimizer().rate(), uninterrupted.optimizer().rate());
+        assert_eq!(nn.tensors(), expected.tensors());
+    }
+
+    #[test]
+    fn test_invalid_scheduler() {
+        let nn = network();
+        for (schedule, key, value, message) in [
+            (
+                Schedule::StepDecay {
+                    step_size: 2,
+                    gamma: 0.5,
+                },
+                "scheduler.step_size",
+                "0",
+                "scheduler `step_size` must be positive",
+            ),
+            (
+                Schedule::one_cycle(10),
+                "scheduler.pct_start",
+                "1.5",
+                "scheduler `total` must be positive and `pct_start` in [0, 1)",
+            ),
+        ] {
+            let trainer = Trainer::new(Optimizer::sgd(0.1), 4, 1)
+                .with_scheduler(Scheduler::new(0.1, schedule));
+            let (tensors, mut metadata) = deserialize(&trainer.to_checkpoint(&nn)).unwrap();
+            for (k, v) in &mut metadata {
+                if k == key {
+                    *v = value.to_string();
+                }
+            }
+            let bytes = serialize(&tensors, &metadata);
+            let err = Trainer::resume(&mut network(), &bytes).unwrap_err();
+            assert_eq!(err.to_string(), format!("invalid checkpoint: {message}"));
+        }
+    }
+
+    #[test]
+    fn test_invalid_metadata() {
+        let (input, output) = data();
+        let mut nn = network();
+        let mut trainer = Trainer::new(Optimizer::sgd(0.1), 4, 1).with_clipping(Clip::Norm(1.0));
+        trainer.train_step(&mut nn, &input, &output);
+        let bytes = trainer.to_checkpoint(&nn);
+        assert!(Trainer::resume(&mut network(), &bytes).is_ok());
+
+        for (key, value, message) in [
+            (
+                "sizes",
+                "[3,5,2]",
+                "sizes `[3,5,2]` doesn't match the network's `[3,5,4,2]`",
+            ),
+            (
+                "batch",
+                "3",
+                "`batch` 3 is past the end of an epoch of 10 rows with `batch_size` 4",
+            ),
+            ("clip.max", "0", "`clip.max` 0 must be positive"),
+            ("clip.max", "-1", "`clip.max` -1 must be positive"),
+            ("clip.max", "NaN", "`clip.max` NaN must be positive"),
+        ] {
+            let (tensors, mut metadata) = deserialize(&bytes).unwrap();
+            for (k, v) in &mut metadata {
+                if k == key {
+                    *v = value.to_string();
+                }
+            }
+            let bytes = serialize(&tensors, &metadata);
+            let err = Trainer::resume(&mut network(), &bytes).unwrap_err();
+            assert_eq!(err.to_string(), format!("invalid checkpoint: {message}"));
+        }
+    }
+}
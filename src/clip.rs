@@ -0,0 +1,82 @@
+use crate::neural_network::NeuralNetwork;
+
+/// Limits the gradient before the optimizer applies it, to keep a single
+/// bad batch from throwing training off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clip {
+    /// Scales the gradients of all parameters together so that their global
+    /// L2 norm is at most `max`, keeping their direction.
+    Norm(f32),
+    /// Clamps every gradient entry to `[-max, max]`.
+    Value(f32),
+}
+
+impl Clip {
+    /// Clips the parameter gradients held by `gradient`, as computed by
+    /// `backprop`, and returns their global L2 norm before clipping.
+    pub fn apply(&self, gradient: &mut NeuralNetwork) -> f32 {
+        let mut parameters = gradient.parameters_mut();
+        let norm = parameters
+            .iter()
+            .flat_map(|(_, g)| g.iter())
+            .map(|x| x * x)
+            .sum::<f32>()
+            .sqrt();
+        match *self {
+            Clip::Norm(max) => {
+                assert!(max > 0.0);
+                if norm > max {
+                    for (_, g) in &mut parameters {
+                        g.scale(max / norm);
+                    }
+                }
+            }
+            Clip::Value(max) => {
+                assert!(max > 0.0);
+                for (_, g) in &mut parameters {
+                    g.apply(|x| x.clamp(-max, max));
+                }
+            }
+        }
+        norm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix;
+
+    fn gradient() -> NeuralNetwork {
+        // Weight [3, 0] and bias [4]: a global norm of 5.
+        let mut gradient = NeuralNetwork::new(&[2, 1]);
+        let mut parameters = gradient.parameters_mut();
+        *parameters[0].1 = Matrix::from_iter(2, 1, vec![3.0, 0.0]);
+        *parameters[1].1 = Matrix::from_iter(1, 1, vec![-4.0]);
+        gradient
+    }
+
+    fn values(gradient: &NeuralNetwork) -> Vec<f32> {
+        let parameters = gradient.parameters();
+        parameters
+            .iter()
+            .flat_map(|(_, g)| g.iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn test_clip() {
+        let mut g = gradient();
+        assert_eq!(Clip::Norm(10.0).apply(&mut g), 5.0);
+        assert_eq!(values(&g), vec![3.0, 0.0, -4.0]);
+
+        assert_eq!(Clip::Norm(1.0).apply(&mut g), 5.0);
+        for (a, b) in values(&g).into_iter().zip([0.6, 0.0, -0.8]) {
+            assert!((a - b).abs() < 1e-6, "{a} != {b}");
+        }
+
+        let mut g = gradient();
+        Clip::Value(2.0).apply(&mut g);
+        assert_eq!(values(&g), vec![2.0, 0.0, -2.0]);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::activation::Activation;
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+use std::fmt::Write;
+
+impl NeuralNetwork {
+    /// Generates a dependency-free Rust module with the weights as static
+    /// arrays and an unrolled `predict(&[f32; INPUTS]) -> [f32; OUTPUTS]`
+    /// that matches [`NeuralNetwork::test`].
+    ///
+    /// Panics if a layer has normalization; dropout is left out.
+    pub fn to_rust_source(&self) -> String {
+        let layers = self.codegen_layers();
+        let sizes = self.sizes();
+        let mut src = String::new();
+
+        writeln!(src, "// Generated by rustml. Do not edit.").unwrap();
+        writeln!(src).unwrap();
+        writeln!(src, "pub const INPUTS: usize = {};", sizes[0]).unwrap();
+        writeln!(src, "pub const OUTPUTS: usize = {};", sizes[layers.len()]).unwrap();
+
+        for (i, (weight, bias, _)) in layers.iter().enumerate() {
+            writeln!(src).unwrap();
+            let rows =
+                (0..weight.rows()).map(|row| array(weight.get_row(row).unwrap(), rust_float));
+            let rows = rows.collect::<Vec<_>>().join(",\n    ");
+            writeln!(
+                src,
+                "static W{i}: [[f32; {}]; {}] = [\n    {rows},\n];",
+                weight.cols(),
+                weight.rows()
+            )
+            .unwrap();
+            writeln!(
+                src,
+                "static B{i}: [f32; {}] = {};",
+                bias.len(),
+                array(bias.iter(), rust_float)
+            )
+            .unwrap();
+        }
+
+        for activation in used(&layers) {
+            let body = match activation {
+                // Branches so that `exp` never overflows, like `Matrix::sigmoid`.
+                Activation::Sigmoid => concat!(
+                    "if x >= 0.0 {\n",
+                    "        1.0 / (1.0 + (-x).exp())\n",
+                    "    } else {\n",
+                    "        x.exp() / (1.0 + x.exp())\n",
+                    "    }"
+                ),
+                Activation::Relu => "x.max(0.0)",
+                Activation::Tanh => "x.tanh()",
+                Activation::Identity => continue,
+            };
+            writeln!(src).unwrap();
+            writeln!(src, "fn {activation}(x: f32) -> f32 {{\n    {body}\n}}").unwrap();
+        }
+
+        writeln!(src).unwrap();
+        writeln!(
+            src,
+            "pub fn predict(x: &[f32; INPUTS]) -> [f32; OUTPUTS] {{"
+        )
+        .unwrap();
+        let mut input = "x".to_string();
+        for (i, (weight, _, activation)) in layers.iter().enumerate() {
+            writeln!(src, "    let mut l{i} = [0.0f32; {}];", weight.cols()).unwrap();
+            for col in 0..weight.cols() {
+                let sum = dot(
+                    &input,
+                    &format!("W{i}"),
+                    &format!("B{i}"),
+                    col,
+                    weight.rows(),
+                );
+                let value = match activation {
+                    Activation::Identity => sum,
+                    _ => format!("{activation}({sum})"),
+                };
+                writeln!(src, "    l{i}[{col}] = {value};").unwrap();
+            }
+            input = format!("l{i}");
+        }
+        writeln!(src, "    {input}\n}}").unwrap();
+        src
+    }
+
+    /// Generates a C header with the same contents as
+    /// [`NeuralNetwork::to_rust_source`]. Every symbol is prefixed with
+    /// `prefix`, e.g. `{prefix}_predict(const float *x, float *y)`.
+    ///
+    /// Panics if `prefix` is not a C identifier.
+    pub fn to_c_header(&self, prefix: &str) -> String {
+        let mut chars = prefix.chars();
+        assert!(
+            chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            "`{prefix}` is not a C identifier"
+        );
+        let layers = self.codegen_layers();
+        let sizes = self.sizes();
+        let upper = prefix.to_uppercase();
+        let mut src = String::new();
+
+        writeln!(src, "/* Generated by rustml. Do not edit. */").unwrap();
+        writeln!(
+            src,
+            "#ifndef {upper}_H\n#define {upper}_H\n\n#include <math.h>\n"
+        )
+        .unwrap();
+        writeln!(src, "#define {upper}_INPUTS {}", sizes[0]).unwrap();
+        writeln!(src, "#define {upper}_OUTPUTS {}", sizes[layers.len()]).unwrap();
+
+        for (i, (weight, bias, _)) in layers.iter().enumerate() {
+            writeln!(src).unwrap();
+            let rows = (0..weight.rows()).map(|row| brace(weight.get_row(row).unwrap()));
+            let rows = rows.collect::<Vec<_>>().join(",\n    ");
+            writeln!(
+                src,
+                "static const float {prefix}_w{i}[{}][{}] = {{\n    {rows},\n}};",
+                weight.rows(),
+                weight.cols()
+            )
+            .unwrap();
+            writeln!(
+                src,
+                "static const float {prefix}_b{i}[{}] = {};",
+                bias.len(),
+                brace(bias.iter())
+            )
+            .unwrap();
+        }
+
+        for activation in used(&layers) {
+            let body = match activation {
+                Activation::Sigmoid => {
+                    "x >= 0.0f ? 1.0f / (1.0f + expf(-x)) : expf(x) / (1.0f + expf(x))"
+                }
+                Activation::Relu => "x > 0.0f ? x : 0.0f",
+                Activation::Tanh => "tanhf(x)",
+                Activation::Identity => continue,
+            };
+            writeln!(src).unwrap();
+            writeln!(
+                src,
+                "static inline float {prefix}_{activation}(float x) {{\n    return {body};\n}}"
+            )
+            .unwrap();
+        }
+
+        writeln!(src).unwrap();
+        writeln!(
+            src,
+            "static inline void {prefix}_predict(const float *x, float *y) {{"
+        )
+        .unwrap();
+        let mut input = "x".to_string();
+        for (i, (weight, _, activation)) in layers.iter().enumerate() {
+            let output = match i + 1 == layers.len() {
+                true => "y".to_string(),
+                false => {
+                    writeln!(src, "    float l{i}[{}];", weight.cols()).unwrap();
+                    format!("l{i}")
+                }
+            };
+            for col in 0..weight.cols() {
+                let (w, b) = (format!("{prefix}_w{i}"), format!("{prefix}_b{i}"));
+                let sum = dot(&input, &w, &b, col, weight.rows());
+                let value = match activation {
+                    Activation::Identity => sum,
+                    _ => format!("{prefix}_{activation}({sum})"),
+                };
+                writeln!(src, "    {output}[{col}] = {value};").unwrap();
+            }
+            input = output;
+        }
+        writeln!(src, "}}\n\n#endif").unwrap();
+        src
+    }
+
+    fn codegen_layers(&self) -> Vec<(&Matrix, &Matrix, Activation)> {
+        let tensors = self.tensors();
+        let activations = self.activations();
+        assert_eq!(
+            tensors.len(),
+            2 * activations.len(),
+            "layers with normalization cannot be exported"
+        );
+        tensors
+            .chunks(2)
+            .zip(activations)
+            .map(|(pair, &activation)| (pair[0].1, pair[1].1, activation))
+            .collect()
+    }
+}
+
+/// The distinct activations in order of first use.
+fn used(layers: &[(&Matrix, &Matrix, Activation)]) -> Vec<Activation> {
+    let mut used = Vec::new();
+    for (_, _, activation) in layers {
+        if !used.contains(activation) {
+            used.push(*activation);
+        }
+    }
+    used
+}
+
+/// The pre-activation of output `col`, summed in the same order as
+/// `Matrix::dot_from` followed by the bias so the results match exactly.
+fn dot(input: &str, weight: &str, bias: &str, col: usize, rows: usize) -> String {
+    let mut sum = (0..rows)
+        .map(|row| format!("{input}[{row}] * {weight}[{row}][{col}]"))
+        .collect::<Vec<_>>()
+        .join(" + ");
+    write!(sum, " + {bias}[{col}]").unwrap();
+    sum
+}
+
+fn rust_float(x: &f32) -> String {
+    match x {
+        x if x.is_nan() => "f32::NAN".to_string(),
+        x if x.is_infinite() && *x > 0.0 => "f32::INFINITY".to_string(),
+        x if x.is_infinite() => "f32::NEG_INFINITY".to_string(),
+        x => format!("{x:?}"),
+    }
+}
+
+fn c_float(x: &f32) -> String {
+    match x {
+        x if x.is_nan() => "NAN".to_string(),
+        x if x.is_infinite() && *x > 0.0 => "INFINITY".to_string(),
+        x if x.is_infinite() => "-INFINITY".to_string(),
+        x => format!("{x:?}f"),
+    }
+}
+
+fn array<'a, I, F>(values: I, f: F) -> String
+where
+    I: Iterator<Item = &'a f32>,
+    F: Fn(&f32) -> String,
+{
+    format!("[{}]", values.map(f).collect::<Vec<_>>().join(", "))
+}
+
+fn brace<'a, I>(values: I) -> String
+where
+    I: Iterator<Item = &'a f32>,
+{
+    format!("{{{}}}", values.map(c_float).collect::<Vec<_>>().join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_source() {
+        let mut nn = NeuralNetwork::from_iter(&[2, 1], std::iter::repeat(0.5));
+        nn.set_activation(0, Activation::Relu);
+        let src = nn.to_rust_source();
+        assert!(src.contains("static W0: [[f32; 1]; 2] = [\n    [0.5],\n    [0.5],\n];"));
+        assert!(src.contains("static B0: [f32; 1] = [0.5];"));
+        assert!(src.contains("    l0[0] = relu(x[0] * W0[0][0] + x[1] * W0[1][0] + B0[0]);"));
+        assert!(!src.contains("fn sigmoid"));
+    }
+
+    #[test]
+    fn test_c_header() {
+        let nn = NeuralNetwork::from_iter(&[1, 2, 1], std::iter::repeat(-0.25));
+        let src = nn.to_c_header("xor");
+        assert!(src.contains("#ifndef XOR_H\n#define XOR_H\n"));
+        assert!(src.contains("#define XOR_INPUTS 1\n#define XOR_OUTPUTS 1\n"));
+        assert!(src.contains("static const float xor_w0[1][2] = {"));
+        assert!(src.contains("static inline float xor_sigmoid(float x) {"));
+        assert!(src.contains("static inline void xor_predict(const float *x, float *y) {"));
+        assert!(src.ends_with("}\n\n#endif\n"));
+        assert!(src.contains("static const float xor_b0[2] = {-0.25f, -0.25f};"));
+        assert!(src.contains("    float l0[2];"));
+        assert!(src.contains(
+            "    y[0] = xor_sigmoid(l0[0] * xor_w1[0][0] + l0[1] * xor_w1[1][0] + xor_b1[0]);"
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "`2fast` is not a C identifier")]
+    fn test_c_prefix() {
+        NeuralNetwork::new(&[1, 1]).to_c_header("2fast");
+    }
+}
@@ -0,0 +1,457 @@
+use crate::activation::Activation;
+use crate::init::Init;
+use crate::json::{Json, JsonError};
+use crate::loss::Loss;
+use crate::neural_network::NeuralNetwork;
+use crate::optimizer::{Method, Optimizer};
+use crate::regularizer::Regularizer;
+use crate::trainer::Trainer;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Json(JsonError),
+    /// `key` is the path to the offending value, e.g. `optimizer.rate` or
+    /// `activations[1]`.
+    Invalid {
+        key: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "{err}"),
+            ConfigError::Json(err) => write!(f, "{err}"),
+            ConfigError::Invalid { key, message } => write!(f, "`{key}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(err: std::io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<JsonError> for ConfigError {
+    fn from(err: JsonError) -> Self {
+        ConfigError::Json(err)
+    }
+}
+
+/// A network and its training setup, read from a JSON file such as
+///
+/// ```json
+/// {
+///     "sizes": [2, 4, 1],
+///     "activations": ["relu", "sigmoid"],
+///     "loss": "mse",
+///     "regularization": {"l2": 0.001},
+///     "optimizer": {"method": "adam", "rate": 0.01},
+///     "batch_size": 4,
+///     "epochs": 500,
+///     "seed": 1
+/// }
+/// ```
+///
+/// Only `sizes` is required. `activations` is either one name for every
+/// layer or one per layer (default `sigmoid`); `loss` defaults to `mse`;
+/// `regularization` is one `{"l1": .., "l2": ..}` penalty for the weights of
+/// every layer or one per layer (default none, either key defaults to 0); the
+/// optimizer defaults to Adam with `rate` 0.01. Momentum takes `momentum`
+/// (default 0.9) and Adam `beta1`, `beta2` and `eps` (defaults as in
+/// [`Optimizer::adam`]); momentum and the betas must be in [0, 1) and `eps`
+/// positive. `batch_size`, `epochs` and `seed` default to 32, 100 and 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub sizes: Vec<usize>,
+    pub activations: Vec<Activation>,
+    pub loss: Loss,
+    pub regularizers: Vec<Option<Regularizer>>,
+    pub method: Method,
+    pub rate: f32,
+    pub batch_size: usize,
+    pub epochs: usize,
+    pub seed: u64,
+}
+
+impl Config {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let json = Json::parse(text)?;
+        let root = &json;
+        check_keys(
+            object(root, "")?,
+            "",
+            &[
+                "sizes",
+                "activations",
+                "loss",
+                "regularization",
+                "optimizer",
+                "batch_size",
+                "epochs",
+                "seed",
+            ],
+        )?;
+
+        let sizes = root
+            .get("sizes")
+            .ok_or_else(|| invalid("sizes", "is required"))?;
+        let sizes = array(sizes, "sizes")?
+            .iter()
+            .enumerate()
+            .map(|(i, size)| positive(size, &format!("sizes[{i}]")))
+            .collect::<Result<Vec<_>, _>>()?;
+        if sizes.len() < 2 {
+            return Err(invalid(
+                "sizes",
+                "needs at least an input and an output size",
+            ));
+        }
+        let layers = sizes.len() - 1;
+
+        let activations = match root.get("activations") {
+            None => vec![Activation::Sigmoid; layers],
+            Some(Json::String(name)) => vec![parse(name, "activations")?; layers],
+            Some(activations) => {
+                let activations = array(activations, "activations")?;
+                if activations.len() != layers {
+                    return Err(invalid(
+                        "activations",
+                        &format!(
+                            "has {} entries but `sizes` describes {layers} layers",
+                            activations.len()
+                        ),
+                    ));
+                }
+                activations
+                    .iter()
+                    .enumerate()
+                    .map(|(i, a)| {
+                        let key = format!("activations[{i}]");
+                        parse(string(a, &key)?, &key)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        let loss = match root.get("loss") {
+            Some(loss) => parse(string(loss, "loss")?, "loss")?,
+            None => Loss::Mse,
+        };
+
+        let regularizers = match root.get("regularization") {
+            None => vec![None; layers],
+            Some(Json::Array(regularizers)) => {
+                if regularizers.len() != layers {
+                    return Err(invalid(
+                        "regularization",
+                        &format!(
+                            "has {} entries but `sizes` describes {layers} layers",
+                            regularizers.len()
+                        ),
+                    ));
+                }
+                regularizers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, r)| Self::regularizer(r, &format!("regularization[{i}]")))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            Some(regularizer) => vec![Self::regularizer(regularizer, "regularization")?; layers],
+        };
+
+        let (method, rate) = match root.get("optimizer") {
+            Some(optimizer) => Self::optimizer(optimizer)?,
+            None => (Optimizer::adam(0.01).method(), 0.01),
+        };
+
+        let usize_or = |key: &str, default: usize| match root.get(key) {
+            Some(value) => positive(value, key),
+            None => Ok(default),
+        };
+        let batch_size = usize_or("batch_size", 32)?;
+        let epochs = usize_or("epochs", 100)?;
+        let seed = match root.get("seed") {
+            Some(seed) => seed
+                .as_usize()
+                .ok_or_else(|| invalid("seed", "must be a non-negative integer"))?
+                as u64,
+            None => 0,
+        };
+
+        Ok(Config {
+            sizes,
+            activations,
+            loss,
+            regularizers,
+            method,
+            rate,
+            batch_size,
+            epochs,
+            seed,
+        })
+    }
+
+    fn optimizer(json: &Json) -> Result<(Method, f32), ConfigError> {
+        let optimizer = json;
+        let method = match optimizer.get("method") {
+            Some(method) => string(method, "optimizer.method")?,
+            None => "adam",
+        };
+        let allowed: &[&str] = match method {
+            "sgd" => &["method", "rate"],
+            "momentum" => &["method", "rate", "momentum"],
+            "adam" => &["method", "rate", "beta1", "beta2", "eps"],
+            _ => {
+                return Err(invalid(
+                    "optimizer.method",
+                    &format!("unknown optimizer `{method}`, expected sgd, momentum or adam"),
+                ))
+            }
+        };
+        check_keys(object(optimizer, "optimizer")?, "optimizer", allowed)?;
+
+        let float = |key: &str, default: f32| -> Result<f32, ConfigError> {
+            let path = format!("optimizer.{key}");
+            match optimizer.get(key) {
+                Some(value) => non_negative(value, &path),
+                None => Ok(default),
+            }
+        };
+        // Momentum and the betas are decay factors, and `eps` guards a
+        // division.
+        let bounded = |key: &str, default: f32, valid: fn(f32) -> bool, expected: &str| {
+            let x = float(key, default)?;
+            match valid(x) {
+                true => Ok(x),
+                false => Err(invalid(&format!("optimizer.{key}"), expected)),
+            }
+        };
+        let fraction =
+            |key: &str, default: f32| bounded(key, default, |x| x < 1.0, "must be less than 1");
+        let method = match method {
+            "sgd" => Method::Sgd,
+            "momentum" => Method::Momentum {
+                momentum: fraction("momentum", 0.9)?,
+            },
+            _ => Method::Adam {
+                beta1: fraction("beta1", 0.9)?,
+                beta2: fraction("beta2", 0.999)?,
+                eps: bounded("eps", 1e-8, |x| x > 0.0, "must be positive")?,
+            },
+        };
+        Ok((method, float("rate", 0.01)?))
+    }
+
+    /// `{"l1": .., "l2": ..}`, or `null` for none.
+    fn regularizer(json: &Json, path: &str) -> Result<Option<Regularizer>, ConfigError> {
+        if let Json::Null = json {
+            return Ok(None);
+        }
+        check_keys(object(json, path)?, path, &["l1", "l2"])?;
+        let strength = |key: &str| match json.get(key) {
+            Some(value) => non_negative(value, &format!("{path}.{key}")),
+            None => Ok(0.0),
+        };
+        Ok(Some(Regularizer {
+            l1: strength("l1")?,
+            l2: strength("l2")?,
+        }))
+    }
+
+    /// A network with the configured sizes, activations, loss and
+    /// regularization, its weights initialized from `seed`.
+    pub fn network(&self) -> NeuralNetwork {
+        let mut nn =
+            NeuralNetwork::with_init(&self.sizes, Init::XavierUniform, Init::Zeros, self.seed);
+        for (layer, activation) in self.activations.iter().enumerate() {
+            nn.set_activation(layer, *activation);
+        }
+        for (layer, regularizer) in self.regularizers.iter().enumerate() {
+            if let Some(regularizer) = regularizer {
+                nn.set_regularizer(layer, *regularizer);
+            }
+        }
+        nn.set_loss(self.loss);
+        nn
+    }
+
+    pub fn trainer(&self) -> Trainer {
+        Trainer::new(
+            Optimizer::new(self.method, self.rate),
+            self.batch_size,
+            self.seed,
+        )
+    }
+}
+
+fn invalid(key: &str, message: &str) -> ConfigError {
+    ConfigError::Invalid {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn check_keys(object: &[(String, Json)], path: &str, allowed: &[&str]) -> Result<(), ConfigError> {
+    match object
+        .iter()
+        .find(|(key, _)| !allowed.contains(&key.as_str()))
+    {
+        Some((key, _)) => {
+            let key = match path.is_empty() {
+                true => key.clone(),
+                false => format!("{path}.{key}"),
+            };
+            Err(invalid(&key, "unknown key"))
+        }
+        None => Ok(()),
+    }
+}
+
+fn object<'a>(json: &'a Json, key: &str) -> Result<&'a [(String, Json)], ConfigError> {
+    let key = if key.is_empty() { "(root)" } else { key };
+    json.as_object()
+        .ok_or_else(|| invalid(key, "must be an object"))
+}
+
+fn array<'a>(json: &'a Json, key: &str) -> Result<&'a [Json], ConfigError> {
+    json.as_array()
+        .ok_or_else(|| invalid(key, "must be an array"))
+}
+
+fn string<'a>(json: &'a Json, key: &str) -> Result<&'a str, ConfigError> {
+    json.as_str()
+        .ok_or_else(|| invalid(key, "must be a string"))
+}
+
+fn positive(json: &Json, key: &str) -> Result<usize, ConfigError> {
+    json.as_usize()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| invalid(key, "must be a positive integer"))
+}
+
+fn non_negative(json: &Json, key: &str) -> Result<f32, ConfigError> {
+    json.as_f64()
+        .map(|x| x as f32)
+        .filter(|x| x.is_finite() && *x >= 0.0)
+        .ok_or_else(|| invalid(key, "must be a non-negative number"))
+}
+
+fn parse<T: std::str::FromStr<Err = String>>(name: &str, key: &str) -> Result<T, ConfigError> {
+    name.parse().map_err(|e: String| invalid(key, &e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn test_parse_and_build() {
+        let config = Config::parse(
+            r#"{
+                "sizes": [2, 4, 1],
+                "activations": ["tanh", "sigmoid"],
+                "loss": "cross_entropy",
+                "optimizer": {"method": "momentum", "rate": 0.5, "momentum": 0.8},
+                "batch_size": 4,
+                "regularization": [{"l2": 0.001}, null],
+                "epochs": 300,
+                "seed": 7
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.method, Method::Momentum { momentum: 0.8 });
+
+        let (mut nn, mut trainer) = (config.network(), config.trainer());
+        assert_eq!(nn.sizes(), vec![2, 4, 1]);
+        assert_eq!(nn.activations(), &[Activation::Tanh, Activation::Sigmoid]);
+        assert_eq!(nn.loss(), Loss::CrossEntropy);
+        assert_eq!(nn.regularizers(), &[Some(Regularizer::l2(0.001)), None]);
+        assert_eq!((trainer.batch_size(), trainer.seed()), (4, 7));
+
+        let input = Matrix::from_iter(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let output = Matrix::from_iter(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
+        let before = nn.cost(&input, &output);
+        for _ in 0..config.epochs {
+            trainer.train_epoch(&mut nn, &input, &output);
+        }
+        assert!(nn.cost(&input, &output) < before / 2.0);
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::parse(r#"{"sizes": [3, 1], "activations": "relu"}"#).unwrap();
+        assert_eq!(config.activations, vec![Activation::Relu]);
+        assert_eq!(config.loss, Loss::Mse);
+        assert_eq!(config.regularizers, vec![None]);
+        assert_eq!(config.method, Optimizer::adam(0.01).method());
+        assert_eq!(config.rate, 0.01);
+        assert_eq!(
+            (config.batch_size, config.epochs, config.seed),
+            (32, 100, 0)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |text: &str| Config::parse(text).unwrap_err().to_string();
+        assert_eq!(error("{}"), "`sizes`: is required");
+        assert_eq!(
+            error(r#"{"sizes": [2, 0]}"#),
+            "`sizes[1]`: must be a positive integer"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 3, 1], "activations": ["relu", "gelu"]}"#),
+            "`activations[1]`: unknown activation `gelu`"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 1], "activations": ["relu", "relu"]}"#),
+            "`activations`: has 2 entries but `sizes` describes 1 layers"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 1], "optimizer": {"method": "sgd", "momentum": 0.9}}"#),
+            "`optimizer.momentum`: unknown key"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 1], "optimizer": {"rate": "fast"}}"#),
+            "`optimizer.rate`: must be a non-negative number"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 1], "optimizer": {"method": "momentum", "momentum": 1}}"#),
+            "`optimizer.momentum`: must be less than 1"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 1], "optimizer": {"beta2": -0.5}}"#),
+            "`optimizer.beta2`: must be a non-negative number"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 1], "optimizer": {"eps": 0}}"#),
+            "`optimizer.eps`: must be positive"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 1], "regularization": {"l1": -0.1}}"#),
+            "`regularization.l1`: must be a non-negative number"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 3, 1], "regularization": [null, {"l3": 1}]}"#),
+            "`regularization[1].l3`: unknown key"
+        );
+        assert_eq!(
+            error(r#"{"sizes": [2, 1], "epoch": 10}"#),
+            "`epoch`: unknown key"
+        );
+        assert!(error(r#"{"sizes": [2, 1],}"#).contains("line 1"));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::data::Fold;
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+use std::fmt;
+
+/// A metric of predictions against targets, such as [`crate::accuracy`].
+pub type Metric = fn(&Matrix, &Matrix) -> f32;
+
+/// The scores of every metric on the test set of every fold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CrossValidation {
+    pub names: Vec<String>,
+    /// `scores[fold][metric]`.
+    pub scores: Vec<Vec<f32>>,
+}
+
+impl CrossValidation {
+    /// The scores of metric `name` on every fold.
+    pub fn fold_scores(&self, name: &str) -> Option<Vec<f32>> {
+        let metric = self.names.iter().position(|n| n == name)?;
+        Some(self.scores.iter().map(|fold| fold[metric]).collect())
+    }
+
+    pub fn mean(&self, name: &str) -> Option<f32> {
+        let scores = self.fold_scores(name)?;
+        Some(scores.iter().sum::<f32>() / scores.len() as f32)
+    }
+
+    /// The population standard deviation over the folds.
+    pub fn std(&self, name: &str) -> Option<f32> {
+        let (scores, mean) = (self.fold_scores(name)?, self.mean(name)?);
+        let variance = scores.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>();
+        Some((variance / scores.len() as f32).sqrt())
+    }
+}
+
+impl fmt::Display for CrossValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, name) in self.names.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let (mean, std) = (self.mean(name).unwrap(), self.std(name).unwrap());
+            write!(f, "{name}: {mean:.6} +/- {std:.6}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Trains a fresh network on the training rows of every fold with
+/// `train(input, target)` and scores its predictions on the test rows.
+///
+/// Get the folds from [`crate::k_fold`] or [`crate::stratified_k_fold`].
+pub fn cross_validate<F>(
+    input: &Matrix,
+    target: &Matrix,
+    folds: &[Fold],
+    mut train: F,
+    metrics: &[(&str, Metric)],
+) -> CrossValidation
+where
+    F: FnMut(&Matrix, &Matrix) -> NeuralNetwork,
+{
+    assert_eq!(input.rows(), target.rows());
+    let scores = folds
+        .iter()
+        .map(|fold| {
+            let mut nn = train(
+                &input.select_rows(&fold.train),
+                &target.select_rows(&fold.train),
+            );
+            let predicted = nn.test(&input.select_rows(&fold.test));
+            let target = target.select_rows(&fold.test);
+            metrics
+                .iter()
+                .map(|(_, metric)| metric(&predicted, &target))
+                .collect()
+        })
+        .collect();
+    CrossValidation {
+        names: metrics.iter().map(|(name, _)| name.to_string()).collect(),
+        scores,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::stratified_k_fold;
+    use crate::metrics::{accuracy, log_loss};
+    use crate::optimizer::Optimizer;
+    use crate::trainer::Trainer;
+
+    #[test]
+    fn test_cross_validate() {
+        // Points above the line y = x.
+        let points = (0..40)
+            .flat_map(|i| [(i % 8) as f32 / 8.0, (i / 8) as f32 / 5.0])
+            .collect::<Vec<_>>();
+        let input = Matrix::from_iter(40, 2, points);
+        let target = Matrix::from_iter(
+            40,
+            1,
+            (0..40).map(|row| (input.get(row, 1) > input.get(row, 0)) as u8 as f32),
+        );
+
+        let folds = stratified_k_fold(&target, 4, 0);
+        let mut trained = 0;
+        let result = cross_validate(
+            &input,
+            &target,
+            &folds,
+            |input, target| {
+                assert_eq!(input.rows(), 30);
+                trained += 1;
+                let mut nn = NeuralNetwork::new(&[2, 1]);
+                let mut trainer = Trainer::new(Optimizer::adam(0.1), 10, 0);
+                for _ in 0..200 {
+                    trainer.train_epoch(&mut nn, input, target);
+                }
+                nn
+            },
+            &[("accuracy", accuracy), ("log_loss", log_loss)],
+        );
+
+        assert_eq!(trained, 4);
+        assert_eq!(result.scores.len(), 4);
+        assert!(result.mean("accuracy").unwrap() > 0.85, "{result}");
+        assert!(result.std("log_loss").unwrap() >= 0.0);
+        assert_eq!(result.mean("f1"), None);
+        assert!(result.to_string().starts_with("accuracy: "));
+    }
+}
@@ -0,0 +1,34 @@
+mod csv;
+mod idx;
+mod split;
+
+pub use self::csv::*;
+pub use self::idx::*;
+pub use self::split::*;
+
+use super::matrix::Matrix;
+
+/// Encodes class labels as one-hot rows.
+pub fn one_hot(labels: &[usize], classes: usize) -> Matrix {
+    let mut result = Matrix::new(labels.len(), classes);
+    for (row, &label) in labels.iter().enumerate() {
+        assert!(label < classes, "label {label} out of range");
+        result.set(row, label, 1.0);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_hot() {
+        let m = one_hot(&[2, 0], 3);
+        assert_eq!(
+            m,
+            Matrix::from_iter(2, 3, vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0])
+        );
+        assert_eq!(m.argmax_rows(), vec![2, 0]);
+    }
+}
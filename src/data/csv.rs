@@ -0,0 +1,432 @@
+use crate::matrix::Matrix;
+use std::fmt;
+use std::path::Path;
+
+/// Selects a CSV column by zero-based position or by header name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Self {
+        Column::Index(index)
+    }
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Self {
+        Column::Name(name.to_string())
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Column::Index(index) => write!(f, "{index}"),
+            Column::Name(name) => write!(f, "`{name}`"),
+        }
+    }
+}
+
+/// What to do with empty, `NA`, `N/A`, `NaN` or `?` fields.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Missing {
+    Error,
+    SkipRow,
+    Fill(f32),
+    /// Replace with the mean of the column's present values.
+    Mean,
+}
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io(std::io::Error),
+    UnknownColumn(Column),
+    NoRows,
+    /// `line` and `column` are one-based, as shown by text editors.
+    Invalid {
+        line: usize,
+        column: usize,
+        kind: InvalidKind,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum InvalidKind {
+    Number(String),
+    Missing,
+    Width { expected: usize, found: usize },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(err) => write!(f, "{err}"),
+            CsvError::UnknownColumn(column) => write!(f, "unknown column {column}"),
+            CsvError::NoRows => write!(f, "no data rows"),
+            CsvError::Invalid { line, column, kind } => {
+                write!(f, "line {line}, column {column}: ")?;
+                match kind {
+                    InvalidKind::Number(text) => write!(f, "invalid number `{text}`"),
+                    InvalidKind::Missing => write!(f, "missing value"),
+                    InvalidKind::Width { expected, found } => {
+                        write!(f, "expected {expected} fields, found {found}")
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+impl From<std::io::Error> for CsvError {
+    fn from(err: std::io::Error) -> Self {
+        CsvError::Io(err)
+    }
+}
+
+/// Reads numeric CSV into row-aligned input and target matrices.
+///
+/// ```no_run
+/// use rustml::{CsvReader, Missing};
+///
+/// let (input, output) = CsvReader::new()
+///     .with_targets(["label"])
+///     .with_missing(Missing::Mean)
+///     .read("data.csv")
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct CsvReader {
+    header: bool,
+    delimiter: char,
+    features: Option<Vec<Column>>,
+    targets: Vec<Column>,
+    missing: Missing,
+}
+
+impl Default for CsvReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvReader {
+    /// A comma-separated reader expecting a header line, with the last column
+    /// as the target and every other column as a feature.
+    pub fn new() -> Self {
+        Self {
+            header: true,
+            delimiter: ',',
+            features: None,
+            targets: Vec::new(),
+            missing: Missing::Error,
+        }
+    }
+
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Feature columns, in order. Defaults to every non-target column.
+    pub fn with_features<I, C>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<Column>,
+    {
+        self.features = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Target columns, in order. Defaults to the last column.
+    pub fn with_targets<I, C>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<Column>,
+    {
+        self.targets = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_missing(mut self, missing: Missing) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<(Matrix, Matrix), CsvError> {
+        self.parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(&self, text: &str) -> Result<(Matrix, Matrix), CsvError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim_end_matches('\r')))
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let header = match self.header {
+            true => lines.next().map(|(_, line)| split(line, self.delimiter)),
+            false => None,
+        };
+
+        // Split every line first so the width is known to resolve columns.
+        let mut records = Vec::new();
+        let mut width = header.as_ref().map(|h| h.len());
+        for (line, text) in lines {
+            let fields = split(text, self.delimiter);
+            let expected = *width.get_or_insert(fields.len());
+            if fields.len() != expected {
+                let found = fields.len();
+                let kind = InvalidKind::Width { expected, found };
+                return Err(CsvError::Invalid {
+                    line,
+                    column: found.min(expected) + 1,
+                    kind,
+                });
+            }
+
+            records.push((line, fields));
+        }
+        let width = width.ok_or(CsvError::NoRows)?;
+
+        let resolve = |column: &Column| match column {
+            Column::Index(index) if *index < width => Ok(*index),
+            Column::Name(name) => header
+                .as_ref()
+                .and_then(|h| h.iter().position(|field| field == name))
+                .ok_or_else(|| CsvError::UnknownColumn(column.clone())),
+            _ => Err(CsvError::UnknownColumn(column.clone())),
+        };
+        let targets = match self.targets.is_empty() {
+            true => vec![width - 1],
+            false => self
+                .targets
+                .iter()
+                .map(resolve)
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        let features = match &self.features {
+            Some(features) => features
+                .iter()
+                .map(resolve)
+                .collect::<Result<Vec<_>, _>>()?,
+            None => (0..width).filter(|c| !targets.contains(c)).collect(),
+        };
+        let used = features
+            .iter()
+            .chain(targets.iter())
+            .copied()
+            .collect::<Vec<_>>();
+
+        // Only the selected columns are parsed, so the others may hold text
+        // such as ids, names or dates.
+        let mut rows = Vec::with_capacity(records.len());
+        for (line, fields) in records {
+            let mut values: Vec<Option<f32>> = vec![None; width];
+            for &column in &used {
+                let field = &fields[column];
+                if !is_missing(field) {
+                    let value = field.parse::<f32>().map_err(|_| CsvError::Invalid {
+                        line,
+                        column: column + 1,
+                        kind: InvalidKind::Number(field.to_string()),
+                    })?;
+                    values[column] = Some(value);
+                }
+            }
+            rows.push((line, values));
+        }
+
+        let mut fill = vec![0.0; width];
+        match self.missing {
+            Missing::Error => {
+                for (line, values) in rows.iter() {
+                    if let Some(&column) = used.iter().find(|&&c| values[c].is_none()) {
+                        let kind = InvalidKind::Missing;
+                        return Err(CsvError::Invalid {
+                            line: *line,
+                            column: column + 1,
+                            kind,
+                        });
+                    }
+                }
+            }
+            Missing::SkipRow => {
+                rows.retain(|(_, values)| used.iter().all(|&c| values[c].is_some()))
+            }
+            Missing::Fill(value) => fill = vec![value; width],
+            Missing::Mean => {
+                for (column, fill) in fill.iter_mut().enumerate() {
+                    let present = rows.iter().filter_map(|(_, values)| values[column]);
+                    let (sum, count) = present.fold((0.0, 0), |(s, n), x| (s + x, n + 1));
+                    *fill = if count > 0 { sum / count as f32 } else { 0.0 };
+                }
+            }
+        }
+        if rows.is_empty() {
+            return Err(CsvError::NoRows);
+        }
+
+        let collect = |columns: &[usize]| {
+            let data = rows
+                .iter()
+                .flat_map(|(_, values)| columns.iter().map(|&c| values[c].unwrap_or(fill[c])));
+            Matrix::from_iter(rows.len(), columns.len(), data.collect::<Vec<_>>())
+        };
+        Ok((collect(&features), collect(&targets)))
+    }
+}
+
+fn is_missing(field: &str) -> bool {
+    matches!(field, "" | "?" | "NA" | "N/A" | "NaN" | "nan")
+}
+
+/// Splits a line into trimmed fields, honouring double quotes (`""` being an
+/// escaped quote).
+fn split(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields.iter().map(|f| f.trim().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str = "\
+a,b,\"label\"
+0,0,0
+0,1,1
+
+1,0,1
+1,1,0
+";
+
+    #[test]
+    fn test_parse_default() {
+        let (input, output) = CsvReader::new().parse(DATA).unwrap();
+        assert_eq!(
+            input,
+            Matrix::from_iter(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0])
+        );
+        assert_eq!(output, Matrix::from_iter(4, 1, vec![0.0, 1.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let text = "1;2;3\n4;5;6\n";
+        let (input, output) = CsvReader::new()
+            .with_header(false)
+            .with_delimiter(';')
+            .with_features([2, 0])
+            .with_targets([1])
+            .parse(text)
+            .unwrap();
+        assert_eq!(input, Matrix::from_iter(2, 2, vec![3.0, 1.0, 6.0, 4.0]));
+        assert_eq!(output, Matrix::from_iter(2, 1, vec![2.0, 5.0]));
+
+        let (_, output) = CsvReader::new()
+            .with_targets(["a", "b"])
+            .parse(DATA)
+            .unwrap();
+        assert_eq!(output.cols(), 2);
+
+        let err = CsvReader::new()
+            .with_targets(["c"])
+            .parse(DATA)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "unknown column `c`");
+
+        let text = "id,name,x,y\n1,ada,0.5,1\n2,bob,1.5,0\n";
+        let (input, output) = CsvReader::new()
+            .with_features(["x"])
+            .with_targets(["y"])
+            .parse(text)
+            .unwrap();
+        assert_eq!(input, Matrix::from_iter(2, 1, vec![0.5, 1.5]));
+        assert_eq!(output, Matrix::from_iter(2, 1, vec![1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_missing() {
+        let text = "x,y\n1,2\nNA,4\n5,\n";
+        let err = CsvReader::new().parse(text).unwrap_err();
+        assert_eq!(err.to_string(), "line 3, column 1: missing value");
+
+        let (input, _) = CsvReader::new()
+            .with_missing(Missing::SkipRow)
+            .parse(text)
+            .unwrap();
+        assert_eq!(input, Matrix::from_iter(1, 1, vec![1.0]));
+
+        let (input, output) = CsvReader::new()
+            .with_missing(Missing::Fill(-1.0))
+            .parse(text)
+            .unwrap();
+        assert_eq!(input, Matrix::from_iter(3, 1, vec![1.0, -1.0, 5.0]));
+        assert_eq!(output, Matrix::from_iter(3, 1, vec![2.0, 4.0, -1.0]));
+
+        let (input, output) = CsvReader::new()
+            .with_missing(Missing::Mean)
+            .parse(text)
+            .unwrap();
+        assert_eq!(input, Matrix::from_iter(3, 1, vec![1.0, 3.0, 5.0]));
+        assert_eq!(output, Matrix::from_iter(3, 1, vec![2.0, 4.0, 3.0]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = CsvReader::new().parse("a,b\n1,2\n3,x\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 3, column 2: invalid number `x`");
+
+        let err = CsvReader::new().parse("a,b\n1,2\n\n3\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 2: expected 2 fields, found 1"
+        );
+
+        assert!(matches!(
+            CsvReader::new().parse("a,b\n"),
+            Err(CsvError::NoRows)
+        ));
+        assert!(matches!(
+            CsvReader::new().read("/nonexistent.csv"),
+            Err(CsvError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_file() {
+        let path = std::env::temp_dir().join("rustml_test_read_file.csv");
+        std::fs::write(&path, DATA).unwrap();
+        let (input, output) = CsvReader::new().read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(input.rows(), 4);
+        assert_eq!(output.rows(), 4);
+    }
+}
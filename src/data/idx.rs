@@ -0,0 +1,204 @@
+use crate::matrix::Matrix;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum IdxError {
+    Io(std::io::Error),
+    /// The first two bytes of an IDX file must be zero.
+    Magic([u8; 2]),
+    DataType(u8),
+    Truncated {
+        expected: usize,
+        found: usize,
+    },
+    /// The dimensions in the header describe more bytes than fit in memory.
+    Size(Vec<usize>),
+    /// A label outside `0..10` in an MNIST label file.
+    Label(f32),
+}
+
+impl fmt::Display for IdxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdxError::Io(err) => write!(f, "{err}"),
+            IdxError::Magic(bytes) => write!(f, "invalid IDX magic {bytes:02x?}"),
+            IdxError::DataType(code) => write!(f, "unsupported IDX data type 0x{code:02x}"),
+            IdxError::Truncated { expected, found } => {
+                write!(f, "expected {expected} bytes, found {found}")
+            }
+            IdxError::Size(dims) => write!(f, "IDX dimensions {dims:?} are too large"),
+            IdxError::Label(label) => write!(f, "invalid MNIST label {label}"),
+        }
+    }
+}
+
+impl std::error::Error for IdxError {}
+
+impl From<std::io::Error> for IdxError {
+    fn from(err: std::io::Error) -> Self {
+        IdxError::Io(err)
+    }
+}
+
+/// Reads an (uncompressed) IDX file, see [`parse_idx`].
+pub fn read_idx<P: AsRef<Path>>(path: P) -> Result<Matrix, IdxError> {
+    parse_idx(&std::fs::read(path)?)
+}
+
+/// Parses the IDX format used by MNIST: two zero bytes, a data type code, the
+/// number of dimensions, one big-endian `u32` per dimension and then the
+/// big-endian values.
+///
+/// The first dimension becomes the rows and the remaining ones are flattened
+/// into the columns, so `n x 28 x 28` images turn into an `n x 784` matrix
+/// and `n` labels into an `n x 1` matrix.
+pub fn parse_idx(bytes: &[u8]) -> Result<Matrix, IdxError> {
+    let header = take(bytes, 0, 4)?;
+    if header[0] != 0 || header[1] != 0 {
+        return Err(IdxError::Magic([header[0], header[1]]));
+    }
+    let (code, ndim) = (header[2], header[3] as usize);
+
+    let size = match code {
+        0x08 | 0x09 => 1,
+        0x0B => 2,
+        0x0C | 0x0D => 4,
+        0x0E => 8,
+        code => return Err(IdxError::DataType(code)),
+    };
+
+    let dims = take(bytes, 4, 4 * ndim)?
+        .chunks(4)
+        .map(|d| u32::from_be_bytes([d[0], d[1], d[2], d[3]]) as usize)
+        .collect::<Vec<_>>();
+    let rows = dims.first().copied().unwrap_or(1);
+    let cols = dims
+        .iter()
+        .skip(1)
+        .try_fold(1usize, |n, &d| n.checked_mul(d));
+    let len = cols.and_then(|cols| cols.checked_mul(rows)?.checked_mul(size));
+    let (Some(cols), Some(len)) = (cols, len) else {
+        return Err(IdxError::Size(dims));
+    };
+
+    let data = take(bytes, 4 + 4 * ndim, len)?;
+    let values = data.chunks(size).map(|b| match code {
+        0x08 => b[0] as f32,
+        0x09 => b[0] as i8 as f32,
+        0x0B => i16::from_be_bytes([b[0], b[1]]) as f32,
+        0x0C => i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f32,
+        0x0D => f32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+        _ => f64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32,
+    });
+    Ok(Matrix::from_iter(rows, cols, values.collect::<Vec<_>>()))
+}
+
+/// Reads MNIST images, scaling the pixels to `[0, 1]`.
+pub fn read_mnist_images<P: AsRef<Path>>(path: P) -> Result<Matrix, IdxError> {
+    let mut images = read_idx(path)?;
+    images.apply(|x| x / 255.0);
+    Ok(images)
+}
+
+/// Reads MNIST labels as one-hot rows over ten classes.
+pub fn read_mnist_labels<P: AsRef<Path>>(path: P) -> Result<Matrix, IdxError> {
+    let labels = read_idx(path)?;
+    let labels = labels
+        .iter()
+        .map(|&x| match (0.0..10.0).contains(&x) && x.fract() == 0.0 {
+            true => Ok(x as usize),
+            false => Err(IdxError::Label(x)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(super::one_hot(&labels, 10))
+}
+
+fn take(bytes: &[u8], start: usize, len: usize) -> Result<&[u8], IdxError> {
+    let end = start.saturating_add(len);
+    bytes.get(start..end).ok_or(IdxError::Truncated {
+        expected: end,
+        found: bytes.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idx(code: u8, dims: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, code, dims.len() as u8];
+        for dim in dims {
+            bytes.extend_from_slice(&dim.to_be_bytes());
+        }
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_images_and_labels() {
+        let images = idx(
+            0x08,
+            &[2, 2, 3],
+            &[0, 1, 2, 3, 4, 5, 250, 251, 252, 253, 254, 255],
+        );
+        let m = parse_idx(&images).unwrap();
+        assert_eq!((m.rows(), m.cols()), (2, 6));
+        assert_eq!(m.get(1, 5), Some(&255.0));
+
+        let labels = parse_idx(&idx(0x08, &[3], &[7, 0, 9])).unwrap();
+        assert_eq!(labels, Matrix::from_iter(3, 1, vec![7.0, 0.0, 9.0]));
+    }
+
+    #[test]
+    fn test_parse_wide_types() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(-2i16).to_be_bytes());
+        data.extend_from_slice(&300i16.to_be_bytes());
+        let m = parse_idx(&idx(0x0B, &[1, 2], &data)).unwrap();
+        assert_eq!(m, Matrix::from_iter(1, 2, vec![-2.0, 300.0]));
+
+        let data = 1.5f32.to_be_bytes();
+        let m = parse_idx(&idx(0x0D, &[1], &data)).unwrap();
+        assert_eq!(m, Matrix::from_iter(1, 1, vec![1.5]));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(parse_idx(&[1, 0, 8, 1]), Err(IdxError::Magic(_))));
+        assert!(matches!(
+            parse_idx(&[0, 0, 0x42, 0]),
+            Err(IdxError::DataType(0x42))
+        ));
+
+        let err = parse_idx(&idx(0x08, &[2, 2], &[1, 2, 3])).unwrap_err();
+        assert_eq!(err.to_string(), "expected 16 bytes, found 15");
+
+        let err = parse_idx(&idx(0x0C, &[u32::MAX, u32::MAX, u32::MAX], &[])).unwrap_err();
+        assert!(matches!(err, IdxError::Size(_)), "{err}");
+    }
+
+    #[test]
+    fn test_read_mnist() {
+        let dir = std::env::temp_dir();
+        let images = dir.join("rustml_test_images.idx3-ubyte");
+        let labels = dir.join("rustml_test_labels.idx1-ubyte");
+        std::fs::write(&images, idx(0x08, &[1, 2, 2], &[0, 51, 102, 255])).unwrap();
+        std::fs::write(&labels, idx(0x08, &[1], &[3])).unwrap();
+
+        let x = read_mnist_images(&images).unwrap();
+        let y = read_mnist_labels(&labels).unwrap();
+        std::fs::remove_file(&images).unwrap();
+        std::fs::remove_file(&labels).unwrap();
+
+        assert_eq!(x, Matrix::from_iter(1, 4, vec![0.0, 0.2, 0.4, 1.0]));
+        assert_eq!(y.cols(), 10);
+        assert_eq!(y.argmax_rows(), vec![3]);
+
+        let labels = dir.join("rustml_test_bad_labels.idx1-ubyte");
+        std::fs::write(&labels, idx(0x08, &[2], &[3, 10])).unwrap();
+        let err = read_mnist_labels(&labels).unwrap_err();
+        std::fs::remove_file(&labels).unwrap();
+        assert_eq!(err.to_string(), "invalid MNIST label 10");
+    }
+}
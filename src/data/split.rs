@@ -0,0 +1,197 @@
+use crate::matrix::{random_permutation, Matrix};
+use crate::metrics::labels;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// Row-aligned inputs and targets, divided into a training and a test part.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Split {
+    pub train_input: Matrix,
+    pub train_target: Matrix,
+    pub test_input: Matrix,
+    pub test_target: Matrix,
+}
+
+impl Split {
+    fn new(input: &Matrix, target: &Matrix, train: &[usize], test: &[usize]) -> Self {
+        Split {
+            train_input: input.select_rows(train),
+            train_target: target.select_rows(train),
+            test_input: input.select_rows(test),
+            test_target: target.select_rows(test),
+        }
+    }
+}
+
+/// Holds out a shuffled `test_fraction` of the rows, rounded to the nearest
+/// row. For a validation set as well, split the training part again.
+pub fn train_test_split(input: &Matrix, target: &Matrix, test_fraction: f32, seed: u64) -> Split {
+    check(input, target, test_fraction);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let rows = random_permutation(input.rows(), &mut rng);
+    let (test, train) = rows.split_at(held_out(rows.len(), test_fraction));
+    Split::new(input, target, train, test)
+}
+
+/// Like [`train_test_split`], but holds out `test_fraction` of the rows of
+/// every class so both parts keep the class proportions of `target`: one-hot
+/// rows, or 0/1 values in a single column.
+pub fn stratified_train_test_split(
+    input: &Matrix,
+    target: &Matrix,
+    test_fraction: f32,
+    seed: u64,
+) -> Split {
+    check(input, target, test_fraction);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (mut train, mut test) = (Vec::new(), Vec::new());
+    for mut rows in classes(target) {
+        rows.shuffle(&mut rng);
+        let (held, kept) = rows.split_at(held_out(rows.len(), test_fraction));
+        test.extend_from_slice(held);
+        train.extend_from_slice(kept);
+    }
+    train.shuffle(&mut rng);
+    test.shuffle(&mut rng);
+    Split::new(input, target, &train, &test)
+}
+
+fn check(input: &Matrix, target: &Matrix, test_fraction: f32) {
+    assert_eq!(input.rows(), target.rows());
+    assert!(
+        test_fraction > 0.0 && test_fraction < 1.0,
+        "test_fraction must be in (0, 1)"
+    );
+}
+
+fn held_out(rows: usize, test_fraction: f32) -> usize {
+    (rows as f32 * test_fraction).round() as usize
+}
+
+/// The rows of every class of `target`, in order.
+fn classes(target: &Matrix) -> Vec<Vec<usize>> {
+    let labels = labels(target);
+    let mut classes = vec![Vec::new(); target.cols().max(2)];
+    for (row, label) in labels.into_iter().enumerate() {
+        classes[label].push(row);
+    }
+    classes
+}
+
+/// The row indices of one cross-validation fold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fold {
+    pub train: Vec<usize>,
+    pub test: Vec<usize>,
+}
+
+/// Divides `rows` shuffled rows into `k` test sets whose sizes differ by at
+/// most one, each fold training on the other rows.
+pub fn k_fold(rows: usize, k: usize, seed: u64) -> Vec<Fold> {
+    assert!(k >= 2 && k <= rows, "k must be in 2..=rows");
+    let mut rng = StdRng::seed_from_u64(seed);
+    folds(random_permutation(rows, &mut rng), k)
+}
+
+/// Like [`k_fold`], but deals the rows of every class out to the folds in
+/// turn so each test set keeps the class proportions of `target`.
+pub fn stratified_k_fold(target: &Matrix, k: usize, seed: u64) -> Vec<Fold> {
+    assert!(k >= 2 && k <= target.rows(), "k must be in 2..=rows");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut rows = Vec::with_capacity(target.rows());
+    for mut class in classes(target) {
+        class.shuffle(&mut rng);
+        rows.extend(class);
+    }
+    folds(rows, k)
+}
+
+/// Assigns `rows[i]` to the test set of fold `i % k`.
+fn folds(rows: Vec<usize>, k: usize) -> Vec<Fold> {
+    (0..k)
+        .map(|fold| {
+            let (test, train) = rows
+                .iter()
+                .enumerate()
+                .partition::<Vec<_>, _>(|(i, _)| i % k == fold);
+            Fold {
+                train: train.into_iter().map(|(_, &row)| row).collect(),
+                test: test.into_iter().map(|(_, &row)| row).collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::one_hot;
+
+    fn labels_of(target: &Matrix) -> Vec<usize> {
+        let mut labels = target.argmax_rows();
+        labels.sort();
+        labels
+    }
+
+    #[test]
+    fn test_train_test_split() {
+        let input = Matrix::from_iter(10, 2, (0..20).map(|x| x as f32));
+        let target = Matrix::from_iter(10, 1, (0..10).map(|x| x as f32));
+        let split = train_test_split(&input, &target, 0.3, 1);
+        assert_eq!(split, train_test_split(&input, &target, 0.3, 1));
+        assert_eq!((split.train_input.rows(), split.test_input.rows()), (7, 3));
+
+        for (row, &y) in split.train_target.iter().enumerate() {
+            let x = split
+                .train_input
+                .get_row(row)
+                .unwrap()
+                .copied()
+                .collect::<Vec<_>>();
+            assert_eq!(x, vec![2.0 * y, 2.0 * y + 1.0]);
+        }
+        let rows = split.train_target.iter().chain(split.test_target.iter());
+        let mut seen = rows.map(|&y| y as usize).collect::<Vec<_>>();
+        seen.sort();
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_stratified() {
+        let classes = [0, 0, 0, 0, 0, 0, 1, 1, 1, 2, 2, 2];
+        let target = one_hot(&classes, 3);
+        let input = Matrix::from_iter(12, 1, (0..12).map(|x| x as f32));
+
+        let split = stratified_train_test_split(&input, &target, 1.0 / 3.0, 4);
+        assert_eq!(labels_of(&split.test_target), vec![0, 0, 1, 2]);
+        assert_eq!(labels_of(&split.train_target), vec![0, 0, 0, 0, 1, 1, 2, 2]);
+
+        let folds = stratified_k_fold(&target, 3, 4);
+        let mut tested = Vec::new();
+        for fold in &folds {
+            let test = labels_of(&target.select_rows(&fold.test));
+            assert_eq!(test, vec![0, 0, 1, 2]);
+            assert_eq!(fold.train.len() + fold.test.len(), 12);
+            assert!(fold.test.iter().all(|row| !fold.train.contains(row)));
+            tested.extend_from_slice(&fold.test);
+        }
+        tested.sort();
+        assert_eq!(tested, (0..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_k_fold() {
+        let folds = k_fold(10, 3, 0);
+        let sizes = folds.iter().map(|f| f.test.len()).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![4, 3, 3]);
+        let mut tested = folds
+            .iter()
+            .flat_map(|f| f.test.clone())
+            .collect::<Vec<_>>();
+        tested.sort();
+        assert_eq!(tested, (0..10).collect::<Vec<_>>());
+        assert_eq!(folds, k_fold(10, 3, 0));
+        assert_ne!(folds, k_fold(10, 3, 1));
+    }
+}
@@ -0,0 +1,131 @@
+use super::matrix::Matrix;
+use rand::Rng;
+
+/// Weight initialization strategies, scaled by the fan-in and fan-out of the
+/// layer being initialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Init {
+    Zeros,
+    /// Glorot & Bengio: `U(-l, l)` with `l = sqrt(6 / (fan_in + fan_out))`.
+    XavierUniform,
+    /// Glorot & Bengio: `N(0, 2 / (fan_in + fan_out))`.
+    XavierNormal,
+    /// He et al.: `U(-l, l)` with `l = sqrt(6 / fan_in)`.
+    HeUniform,
+    /// He et al.: `N(0, 2 / fan_in)`.
+    HeNormal,
+    /// LeCun: `U(-l, l)` with `l = sqrt(3 / fan_in)`.
+    LeCunUniform,
+    /// LeCun: `N(0, 1 / fan_in)`.
+    LeCunNormal,
+    /// A (semi-)orthogonal matrix obtained from Gram-Schmidt on Gaussian noise.
+    Orthogonal,
+}
+
+impl Init {
+    /// Samples a `rows x cols` matrix for a layer with the given fans.
+    pub fn sample<R>(
+        &self,
+        rows: usize,
+        cols: usize,
+        fan_in: usize,
+        fan_out: usize,
+        rng: &mut R,
+    ) -> Matrix
+    where
+        R: Rng,
+    {
+        let (fan_in, fan_out) = (fan_in as f32, fan_out as f32);
+        let uniform =
+            |limit: f32, rng: &mut R| Matrix::random_uniform(rows, cols, -limit, limit, rng);
+        let normal = |std: f32, rng: &mut R| Matrix::random_normal(rows, cols, 0.0, std, rng);
+
+        match self {
+            Init::Zeros => Matrix::new(rows, cols),
+            Init::XavierUniform => uniform((6.0 / (fan_in + fan_out)).sqrt(), rng),
+            Init::XavierNormal => normal((2.0 / (fan_in + fan_out)).sqrt(), rng),
+            Init::HeUniform => uniform((6.0 / fan_in).sqrt(), rng),
+            Init::HeNormal => normal((2.0 / fan_in).sqrt(), rng),
+            Init::LeCunUniform => uniform((3.0 / fan_in).sqrt(), rng),
+            Init::LeCunNormal => normal((1.0 / fan_in).sqrt(), rng),
+            Init::Orthogonal => orthogonal(rows, cols, rng),
+        }
+    }
+}
+
+fn orthogonal<R: Rng>(rows: usize, cols: usize, rng: &mut R) -> Matrix {
+    // Orthonormalize the columns of a tall matrix, transposing if needed.
+    let (tall, wide) = (rows.max(cols), rows.min(cols));
+    let mut q = Matrix::random_normal(wide, tall, 0.0, 1.0, rng);
+
+    let data = q.as_mut_slice();
+    for i in 0..wide {
+        let (done, rest) = data.split_at_mut(i * tall);
+        let v = &mut rest[..tall];
+        for u in done.chunks(tall) {
+            let dot = u.iter().zip(v.iter()).map(|(a, b)| a * b).sum::<f32>();
+            v.iter_mut().zip(u).for_each(|(b, a)| *b -= dot * a);
+        }
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+
+    match rows >= cols {
+        true => q.transpose(),
+        false => q,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_uniform_bounds() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let m = Init::XavierUniform.sample(20, 30, 20, 30, &mut rng);
+        let limit = (6.0f32 / 50.0).sqrt();
+        assert!(m.iter().all(|x| x.abs() <= limit));
+        assert!(m.iter().any(|&x| x != m[0]));
+    }
+
+    #[test]
+    fn test_normal_std() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let m = Init::HeNormal.sample(200, 100, 200, 100, &mut rng);
+        let n = m.len() as f32;
+        let mean = m.iter().sum::<f32>() / n;
+        let std = (m.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / n).sqrt();
+        assert!(mean.abs() < 0.01);
+        assert!((std - 0.1).abs() < 0.005, "{std}");
+    }
+
+    #[test]
+    fn test_orthogonal() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for (rows, cols) in [(5, 3), (3, 5), (4, 4)] {
+            let m = Init::Orthogonal.sample(rows, cols, rows, cols, &mut rng);
+            assert_eq!((m.rows(), m.cols()), (rows, cols));
+
+            let gram = match rows >= cols {
+                true => &m.transpose() * &m,
+                false => &m * &m.transpose(),
+            };
+            for i in 0..gram.rows() {
+                for j in 0..gram.cols() {
+                    let expected = if i == j { 1.0 } else { 0.0 };
+                    assert!((gram.get(i, j).unwrap() - expected).abs() < 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_seeded() {
+        let a = Init::LeCunNormal.sample(3, 3, 3, 3, &mut StdRng::seed_from_u64(9));
+        let b = Init::LeCunNormal.sample(3, 3, 3, 3, &mut StdRng::seed_from_u64(9));
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,71 @@
+mod attention;
+mod dropout;
+mod embedding;
+mod linear;
+mod norm;
+mod transformer;
+
+pub use self::attention::*;
+pub use self::dropout::*;
+pub use self::embedding::*;
+pub use self::linear::*;
+pub use self::norm::*;
+pub use self::transformer::*;
+
+use super::matrix::Matrix;
+
+/// Whether stochastic layers such as [`Dropout`] are active.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    #[default]
+    Train,
+    Eval,
+}
+
+/// A differentiable building block operating on `rows x features` matrices.
+///
+/// Gradients follow the same convention as `NeuralNetwork::finite_diff`: they
+/// are written into `gradient`, a value of the same shape as the layer
+/// (usually a clone of it), and applied with `learn`.
+pub trait Layer {
+    fn forward(&mut self, input: &Matrix) -> Matrix;
+
+    /// Propagates `output_grad` through the last `forward` call, storing the
+    /// parameter gradients in `gradient` and returning the input gradient.
+    fn backward(&mut self, gradient: &mut Self, output_grad: &Matrix) -> Matrix;
+
+    fn learn(&mut self, gradient: &mut Self, rate: &f32);
+}
+
+#[cfg(test)]
+pub(crate) fn assert_close(a: &Matrix, b: &Matrix, tolerance: f32) {
+    assert_eq!(a.rows(), b.rows());
+    assert_eq!(a.cols(), b.cols());
+    for (x, y) in a.iter().zip(b.iter()) {
+        assert!(
+            (x - y).abs() <= tolerance * (1.0 + y.abs()),
+            "{x} != {y}\nleft:\n{a}right:\n{b}"
+        );
+    }
+}
+
+/// Numerical gradient of `f` with respect to every entry of `x`.
+#[cfg(test)]
+pub(crate) fn numeric_grad<F>(x: &Matrix, mut f: F) -> Matrix
+where
+    F: FnMut(&Matrix) -> f32,
+{
+    let eps = 1e-2;
+    let mut grad = Matrix::new(x.rows(), x.cols());
+    let mut probe = x.clone();
+    for i in 0..x.len() {
+        let value = x[i];
+        probe.as_mut_slice()[i] = value + eps;
+        let plus = f(&probe);
+        probe.as_mut_slice()[i] = value - eps;
+        let minus = f(&probe);
+        probe.as_mut_slice()[i] = value;
+        grad.as_mut_slice()[i] = (plus - minus) / (2.0 * eps);
+    }
+    grad
+}
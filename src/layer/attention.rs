@@ -0,0 +1,234 @@
+use super::{Layer, Linear};
+use crate::matrix::Matrix;
+
+/// `softmax(q * k^T / sqrt(d)) * v` over a `seq_len x d` sequence.
+///
+/// With `causal` set, position `i` only attends to positions `0..=i`.
+#[derive(Clone, Debug)]
+pub struct ScaledDotProductAttention {
+    causal: bool,
+    query: Matrix,
+    key: Matrix,
+    value: Matrix,
+    weights: Matrix,
+}
+
+impl ScaledDotProductAttention {
+    pub fn new(causal: bool) -> Self {
+        Self {
+            causal,
+            query: Matrix::new(0, 0),
+            key: Matrix::new(0, 0),
+            value: Matrix::new(0, 0),
+            weights: Matrix::new(0, 0),
+        }
+    }
+
+    /// The attention weights of the last `forward` call, one row per query.
+    pub fn weights(&self) -> &Matrix {
+        &self.weights
+    }
+
+    pub fn forward(&mut self, query: &Matrix, key: &Matrix, value: &Matrix) -> Matrix {
+        assert_eq!(query.cols(), key.cols());
+        assert_eq!(key.rows(), value.rows());
+
+        let mut scores = query * &key.transpose();
+        scores.scale(1.0 / (query.cols() as f32).sqrt());
+        if self.causal {
+            for row in 0..scores.rows() {
+                for col in row + 1..scores.cols() {
+                    scores.set(row, col, f32::NEG_INFINITY);
+                }
+            }
+        }
+        scores.softmax_rows();
+
+        let output = &scores * value;
+        self.query = query.clone();
+        self.key = key.clone();
+        self.value = value.clone();
+        self.weights = scores;
+        output
+    }
+
+    /// Returns the gradients with respect to the query, key and value.
+    pub fn backward(&mut self, output_grad: &Matrix) -> (Matrix, Matrix, Matrix) {
+        let value_grad = &self.weights.transpose() * output_grad;
+        let weights_grad = output_grad * &self.value.transpose();
+
+        // Softmax backward, row by row: p * (dp - sum(dp * p)).
+        let mut scores_grad = weights_grad;
+        let cols = scores_grad.cols();
+        let rows = scores_grad.as_mut_slice().chunks_mut(cols);
+        for (row, p) in rows.zip(self.weights.chunks(cols)) {
+            let dot = row.iter().zip(p).map(|(d, p)| d * p).sum::<f32>();
+            row.iter_mut().zip(p).for_each(|(d, p)| *d = p * (*d - dot));
+        }
+        scores_grad.scale(1.0 / (self.query.cols() as f32).sqrt());
+
+        let query_grad = &scores_grad * &self.key;
+        let key_grad = &scores_grad.transpose() * &self.query;
+        (query_grad, key_grad, value_grad)
+    }
+}
+
+/// Self-attention with `heads` heads of size `model / heads`.
+#[derive(Clone, Debug)]
+pub struct MultiHeadAttention {
+    heads: Vec<ScaledDotProductAttention>,
+    query: Linear,
+    key: Linear,
+    value: Linear,
+    output: Linear,
+}
+
+impl MultiHeadAttention {
+    pub fn new(model: usize, heads: usize, causal: bool) -> Self {
+        Self::from_iter(model, heads, causal, std::iter::repeat(0.0))
+    }
+
+    /// Fills the query, key, value and output projections, in that order, from
+    /// a single iterator.
+    pub fn from_iter<I>(model: usize, heads: usize, causal: bool, iter: I) -> Self
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        assert!(heads > 0);
+        assert_eq!(model % heads, 0);
+
+        let mut iter = iter.into_iter();
+        Self {
+            heads: vec![ScaledDotProductAttention::new(causal); heads],
+            query: Linear::from_iter(model, model, iter.by_ref()),
+            key: Linear::from_iter(model, model, iter.by_ref()),
+            value: Linear::from_iter(model, model, iter.by_ref()),
+            output: Linear::from_iter(model, model, iter.by_ref()),
+        }
+    }
+
+    pub fn heads(&self) -> &[ScaledDotProductAttention] {
+        &self.heads
+    }
+
+    fn head_size(&self) -> usize {
+        self.query.weight().cols() / self.heads.len()
+    }
+}
+
+impl Layer for MultiHeadAttention {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        let query = self.query.forward(input);
+        let key = self.key.forward(input);
+        let value = self.value.forward(input);
+
+        let size = self.head_size();
+        let mut concat = Matrix::new(input.rows(), query.cols());
+        for (h, head) in self.heads.iter_mut().enumerate() {
+            let (start, end) = (h * size, (h + 1) * size);
+            let output = head.forward(
+                &query.slice_cols(start, end),
+                &key.slice_cols(start, end),
+                &value.slice_cols(start, end),
+            );
+            concat.set_cols(start, &output);
+        }
+
+        self.output.forward(&concat)
+    }
+
+    fn backward(&mut self, gradient: &mut Self, output_grad: &Matrix) -> Matrix {
+        let concat_grad = self.output.backward(&mut gradient.output, output_grad);
+
+        let size = self.head_size();
+        let mut query_grad = Matrix::new(concat_grad.rows(), concat_grad.cols());
+        let mut key_grad = query_grad.clone();
+        let mut value_grad = query_grad.clone();
+        for (h, head) in self.heads.iter_mut().enumerate() {
+            let start = h * size;
+            let (q, k, v) = head.backward(&concat_grad.slice_cols(start, start + size));
+            query_grad.set_cols(start, &q);
+            key_grad.set_cols(start, &k);
+            value_grad.set_cols(start, &v);
+        }
+
+        let mut input_grad = self.query.backward(&mut gradient.query, &query_grad);
+        input_grad.add_from(&self.key.backward(&mut gradient.key, &key_grad));
+        input_grad.add_from(&self.value.backward(&mut gradient.value, &value_grad));
+        input_grad
+    }
+
+    fn learn(&mut self, gradient: &mut Self, rate: &f32) {
+        self.query.learn(&mut gradient.query, rate);
+        self.key.learn(&mut gradient.key, rate);
+        self.value.learn(&mut gradient.value, rate);
+        self.output.learn(&mut gradient.output, rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::{assert_close, numeric_grad};
+
+    fn sequence(rows: usize, cols: usize, seed: f32) -> Matrix {
+        Matrix::from_iter(rows, cols, (0..).map(|i| ((i as f32 + seed) * 0.7).sin()))
+    }
+
+    #[test]
+    fn test_causal_mask() {
+        let mut attention = ScaledDotProductAttention::new(true);
+        let x = sequence(3, 2, 0.0);
+        let output = attention.forward(&x, &x, &x);
+
+        let weights = attention.weights();
+        assert_eq!(weights.get(0, 0), Some(&1.0));
+        assert_eq!(weights.get(0, 1), Some(&0.0));
+        assert_eq!(weights.get(1, 2), Some(&0.0));
+        for row in 0..3 {
+            let sum = weights.get_row(row).unwrap().sum::<f32>();
+            assert!((sum - 1.0).abs() < 1e-6);
+        }
+        assert_eq!(output.get_row_matrix(0), x.get_row_matrix(0));
+    }
+
+    #[test]
+    fn test_attention_backward() {
+        let q = sequence(3, 2, 0.0);
+        let k = sequence(3, 2, 1.0);
+        let v = sequence(3, 2, 2.0);
+        let output_grad = sequence(3, 2, 3.0);
+        let loss = |q: &Matrix, k: &Matrix, v: &Matrix| {
+            let mut y = ScaledDotProductAttention::new(true).forward(q, k, v);
+            y.mul_from(&output_grad);
+            y.iter().sum::<f32>()
+        };
+
+        let mut attention = ScaledDotProductAttention::new(true);
+        attention.forward(&q, &k, &v);
+        let (dq, dk, dv) = attention.backward(&output_grad);
+
+        assert_close(&dq, &numeric_grad(&q, |q| loss(q, &k, &v)), 1e-2);
+        assert_close(&dk, &numeric_grad(&k, |k| loss(&q, k, &v)), 1e-2);
+        assert_close(&dv, &numeric_grad(&v, |v| loss(&q, &k, v)), 1e-2);
+    }
+
+    #[test]
+    fn test_multi_head_backward() {
+        let weights = (0..).map(|i| ((i as f32) * 1.3).cos() * 0.5);
+        let mut attention = MultiHeadAttention::from_iter(4, 2, true, weights);
+        let input = sequence(3, 4, 0.5);
+        let output_grad = sequence(3, 4, 1.5);
+
+        attention.forward(&input);
+        let mut gradient = attention.clone();
+        let input_grad = attention.backward(&mut gradient, &output_grad);
+
+        let expected = numeric_grad(&input, |x| {
+            let mut y = attention.clone().forward(x);
+            y.mul_from(&output_grad);
+            y.iter().sum()
+        });
+        assert_close(&input_grad, &expected, 1e-2);
+    }
+}
@@ -0,0 +1,107 @@
+use super::{Layer, Mode};
+use crate::matrix::Matrix;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Inverted dropout: in [`Mode::Train`] every input is zeroed with
+/// probability `rate` and the survivors are scaled by `1 / (1 - rate)`, so
+/// [`Mode::Eval`] is simply the identity.
+#[derive(Clone, Debug)]
+pub struct Dropout {
+    rate: f32,
+    mode: Mode,
+    rng: StdRng,
+    mask: Matrix,
+}
+
+impl Dropout {
+    pub fn new(rate: f32, seed: u64) -> Self {
+        assert!((0.0..1.0).contains(&rate));
+        Self {
+            rate,
+            mode: Mode::Train,
+            rng: StdRng::seed_from_u64(seed),
+            mask: Matrix::new(0, 0),
+        }
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /// Restarts the random masks as if the layer had been created with `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// The scaling applied by the last `forward` call; all ones in eval mode.
+    pub fn mask(&self) -> &Matrix {
+        &self.mask
+    }
+}
+
+impl Layer for Dropout {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        let keep = 1.0 - self.rate;
+        self.mask = match self.mode {
+            Mode::Train => {
+                let mut mask =
+                    Matrix::random_bernoulli(input.rows(), input.cols(), keep, &mut self.rng);
+                mask.scale(1.0 / keep);
+                mask
+            }
+            Mode::Eval => Matrix::from_iter(input.rows(), input.cols(), std::iter::repeat(1.0)),
+        };
+
+        let mut output = input.clone();
+        output.mul_from(&self.mask);
+        output
+    }
+
+    fn backward(&mut self, _gradient: &mut Self, output_grad: &Matrix) -> Matrix {
+        let mut input_grad = output_grad.clone();
+        input_grad.mul_from(&self.mask);
+        input_grad
+    }
+
+    fn learn(&mut self, _gradient: &mut Self, _rate: &f32) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropout_train() {
+        let mut dropout = Dropout::new(0.25, 7);
+        let input = Matrix::from_iter(100, 40, std::iter::repeat(1.0));
+        let output = dropout.forward(&input);
+
+        let dropped = output.iter().filter(|&&x| x == 0.0).count() as f32 / 4000.0;
+        assert!((dropped - 0.25).abs() < 0.03, "{dropped}");
+        assert!(output.iter().all(|&x| x == 0.0 || x == 1.0 / 0.75));
+
+        let mut gradient = dropout.clone();
+        let input_grad = dropout.backward(&mut gradient, &input);
+        assert_eq!(input_grad, output);
+    }
+
+    #[test]
+    fn test_dropout_eval_and_seed() {
+        let input = Matrix::from_iter(4, 4, (0..).map(|x| x as f32));
+        let mut a = Dropout::new(0.5, 1);
+        let mut b = Dropout::new(0.5, 1);
+        assert_eq!(a.forward(&input), b.forward(&input));
+
+        a.set_mode(Mode::Eval);
+        assert_eq!(a.forward(&input), input);
+    }
+}
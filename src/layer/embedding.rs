@@ -0,0 +1,186 @@
+use super::Layer;
+use crate::matrix::Matrix;
+
+/// Lookup table mapping integer token ids to learned `1 x dim` vectors.
+///
+/// As a [`Layer`], every row of the input holds `n` token ids (stored as
+/// `f32`) and becomes a row of `n * dim` concatenated embeddings, ready to be
+/// fed into a `NeuralNetwork`. Gradients are sparse: `backward` only records
+/// the rows that were looked up and `learn` only updates those.
+#[derive(Clone, Debug)]
+pub struct Embedding {
+    weight: Matrix,
+    padding: Option<usize>,
+    ids: Vec<usize>,
+    touched: Vec<usize>,
+}
+
+impl Embedding {
+    pub fn new(vocab: usize, dim: usize) -> Self {
+        Self::from_iter(vocab, dim, std::iter::repeat(0.0))
+    }
+
+    pub fn from_iter<I>(vocab: usize, dim: usize, iter: I) -> Self
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        Self {
+            weight: Matrix::from_iter(vocab, dim, iter),
+            padding: None,
+            ids: Vec::new(),
+            touched: Vec::new(),
+        }
+    }
+
+    /// Marks `index` as padding: it always embeds to zeros and never learns.
+    pub fn with_padding(mut self, index: usize) -> Self {
+        assert!(index < self.vocab());
+        let dim = self.dim();
+        self.weight.as_mut_slice()[index * dim..(index + 1) * dim].fill(0.0);
+        self.padding = Some(index);
+        self
+    }
+
+    pub fn vocab(&self) -> usize {
+        self.weight.rows()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.weight.cols()
+    }
+
+    pub fn weight(&self) -> &Matrix {
+        &self.weight
+    }
+
+    /// Rows with a pending gradient, in the order they were first seen.
+    pub fn touched(&self) -> &[usize] {
+        &self.touched
+    }
+
+    /// Looks up `ids`, returning one row per id.
+    pub fn lookup(&self, ids: &[usize]) -> Matrix {
+        let dim = self.dim();
+        let mut output = Matrix::new(ids.len(), dim);
+        for (row, &id) in output.as_mut_slice().chunks_mut(dim).zip(ids) {
+            assert!(id < self.vocab(), "token id {id} out of range");
+            if Some(id) != self.padding {
+                row.copy_from_slice(&self.weight[id * dim..(id + 1) * dim]);
+            }
+        }
+        output
+    }
+}
+
+impl Layer for Embedding {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        let vocab = self.vocab();
+        self.ids = input
+            .iter()
+            .map(|&x| {
+                assert!(
+                    x >= 0.0 && x.fract() == 0.0 && x < vocab as f32,
+                    "token id {x} is not an integer in 0..{vocab}"
+                );
+                x as usize
+            })
+            .collect();
+        let output = self.lookup(&self.ids);
+        Matrix::from_iter(
+            input.rows(),
+            input.cols() * self.dim(),
+            output.iter().copied(),
+        )
+    }
+
+    /// Token ids are not differentiable, so the returned input gradient is
+    /// all zeros.
+    fn backward(&mut self, gradient: &mut Self, output_grad: &Matrix) -> Matrix {
+        let dim = self.dim();
+        gradient.touched.clear();
+
+        for (&id, grad) in self.ids.iter().zip(output_grad.chunks(dim)) {
+            if Some(id) == self.padding {
+                continue;
+            }
+            let row = &mut gradient.weight.as_mut_slice()[id * dim..(id + 1) * dim];
+            if !gradient.touched.contains(&id) {
+                gradient.touched.push(id);
+                row.fill(0.0);
+            }
+            row.iter_mut().zip(grad).for_each(|(a, b)| *a += b);
+        }
+
+        Matrix::new(output_grad.rows(), output_grad.cols() / dim)
+    }
+
+    fn learn(&mut self, gradient: &mut Self, rate: &f32) {
+        let dim = self.dim();
+        for &id in gradient.touched.iter() {
+            let row = &mut self.weight.as_mut_slice()[id * dim..(id + 1) * dim];
+            let grad = &gradient.weight[id * dim..(id + 1) * dim];
+            row.iter_mut().zip(grad).for_each(|(w, g)| *w -= rate * g);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedding_forward() {
+        let mut embedding = Embedding::from_iter(3, 2, (0..).map(|x| x as f32)).with_padding(0);
+        let ids = Matrix::from_iter(2, 2, vec![1.0, 2.0, 0.0, 1.0]);
+
+        assert_eq!(
+            embedding.forward(&ids),
+            Matrix::from_iter(2, 4, vec![2.0, 3.0, 4.0, 5.0, 0.0, 0.0, 2.0, 3.0])
+        );
+        assert_eq!(
+            embedding.weight().get_row_matrix(0),
+            Some(Matrix::new(1, 2))
+        );
+    }
+
+    #[test]
+    fn test_embedding_sparse_update() {
+        let mut embedding = Embedding::from_iter(4, 2, std::iter::repeat(1.0)).with_padding(3);
+        let mut gradient = embedding.clone();
+
+        let ids = Matrix::from_iter(1, 3, vec![1.0, 3.0, 1.0]);
+        embedding.forward(&ids);
+        let output_grad = Matrix::from_iter(1, 6, vec![1.0, 2.0, 5.0, 5.0, 3.0, 4.0]);
+        let input_grad = embedding.backward(&mut gradient, &output_grad);
+
+        assert_eq!(input_grad, Matrix::new(1, 3));
+        assert_eq!(gradient.touched(), &[1]);
+        embedding.learn(&mut gradient, &0.5);
+
+        assert_eq!(
+            embedding.weight(),
+            &Matrix::from_iter(4, 2, vec![1.0, 1.0, -1.0, -2.0, 1.0, 1.0, 0.0, 0.0])
+        );
+
+        embedding.forward(&Matrix::from_iter(1, 1, vec![2.0]));
+        embedding.backward(&mut gradient, &Matrix::from_iter(1, 2, vec![1.0, 1.0]));
+        assert_eq!(gradient.touched(), &[2]);
+        embedding.learn(&mut gradient, &1.0);
+        assert_eq!(
+            embedding.weight().get_row_matrix(1),
+            Some(Matrix::from_iter(1, 2, vec![-1.0, -2.0]))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "token id 1.5 is not an integer in 0..3")]
+    fn test_embedding_fractional_id() {
+        Embedding::new(3, 2).forward(&Matrix::from_iter(1, 1, vec![1.5]));
+    }
+
+    #[test]
+    #[should_panic(expected = "token id NaN is not an integer in 0..3")]
+    fn test_embedding_nan_id() {
+        Embedding::new(3, 2).forward(&Matrix::from_iter(1, 1, vec![f32::NAN]));
+    }
+}
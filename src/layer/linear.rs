@@ -0,0 +1,85 @@
+use super::Layer;
+use crate::matrix::Matrix;
+
+/// Fully connected layer computing `input * weight + bias`.
+#[derive(Clone, Debug)]
+pub struct Linear {
+    weight: Matrix,
+    bias: Matrix,
+    input: Matrix,
+}
+
+impl Linear {
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        Self::from_iter(inputs, outputs, std::iter::repeat(0.0))
+    }
+
+    /// Fills the weight and then the bias from a single iterator.
+    pub fn from_iter<I>(inputs: usize, outputs: usize, iter: I) -> Self
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        let mut iter = iter.into_iter();
+        Self {
+            weight: Matrix::from_iter(inputs, outputs, iter.by_ref()),
+            bias: Matrix::from_iter(1, outputs, iter.by_ref()),
+            input: Matrix::new(0, inputs),
+        }
+    }
+
+    pub fn weight(&self) -> &Matrix {
+        &self.weight
+    }
+
+    pub fn bias(&self) -> &Matrix {
+        &self.bias
+    }
+}
+
+impl Layer for Linear {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        self.input = input.clone();
+        let mut output = input * &self.weight;
+        output.add_row_from(&self.bias);
+        output
+    }
+
+    fn backward(&mut self, gradient: &mut Self, output_grad: &Matrix) -> Matrix {
+        gradient.weight = &self.input.transpose() * output_grad;
+        gradient.bias = output_grad.sum_rows();
+        output_grad * &self.weight.transpose()
+    }
+
+    fn learn(&mut self, gradient: &mut Self, rate: &f32) {
+        self.weight.add_scaled_from(&gradient.weight, -rate);
+        self.bias.add_scaled_from(&gradient.bias, -rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::{assert_close, numeric_grad};
+
+    #[test]
+    fn test_linear_backward() {
+        let mut layer = Linear::from_iter(3, 2, (0..8).map(|x| x as f32 * 0.1 - 0.3));
+        let input = Matrix::from_iter(2, 3, vec![0.5, -1.0, 2.0, 1.5, 0.0, -0.5]);
+
+        layer.forward(&input);
+        let mut gradient = layer.clone();
+        let output_grad = Matrix::from_iter(2, 2, vec![1.0, 1.0, 1.0, 1.0]);
+        let input_grad = layer.backward(&mut gradient, &output_grad);
+
+        let expected = numeric_grad(&input, |x| layer.clone().forward(x).iter().sum());
+        assert_close(&input_grad, &expected, 1e-3);
+
+        let expected = numeric_grad(&layer.weight, |w| {
+            let mut probe = layer.clone();
+            probe.weight = w.clone();
+            probe.forward(&input).iter().sum()
+        });
+        assert_close(&gradient.weight, &expected, 1e-3);
+        assert_eq!(gradient.bias, Matrix::from_iter(1, 2, vec![2.0, 2.0]));
+    }
+}
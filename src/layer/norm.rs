@@ -0,0 +1,461 @@
+use super::{Layer, Mode};
+use crate::matrix::Matrix;
+
+/// Normalizes every row to zero mean and unit variance, followed by a learned
+/// per-feature scale (`gamma`) and shift (`beta`).
+#[derive(Clone, Debug)]
+pub struct LayerNorm {
+    gamma: Matrix,
+    beta: Matrix,
+    eps: f32,
+    normalized: Matrix,
+    inv_std: Vec<f32>,
+}
+
+impl LayerNorm {
+    pub fn new(features: usize) -> Self {
+        Self {
+            gamma: Matrix::from_iter(1, features, std::iter::repeat(1.0)),
+            beta: Matrix::new(1, features),
+            eps: 1e-5,
+            normalized: Matrix::new(0, features),
+            inv_std: Vec::new(),
+        }
+    }
+
+    pub fn gamma(&self) -> &Matrix {
+        &self.gamma
+    }
+
+    pub fn beta(&self) -> &Matrix {
+        &self.beta
+    }
+
+    /// Same as `forward`, without keeping anything for `backward`.
+    pub fn infer(&self, input: &Matrix) -> Matrix {
+        self.affine(&self.normalize(input).0)
+    }
+
+    fn normalize(&self, input: &Matrix) -> (Matrix, Vec<f32>) {
+        let cols = input.cols();
+        let mut normalized = input.clone();
+        let mut inv_stds = Vec::with_capacity(input.rows());
+
+        for row in normalized.as_mut_slice().chunks_mut(cols) {
+            let mean = row.iter().sum::<f32>() / cols as f32;
+            let var = row.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / cols as f32;
+            let inv_std = 1.0 / (var + self.eps).sqrt();
+            row.iter_mut().for_each(|x| *x = (*x - mean) * inv_std);
+            inv_stds.push(inv_std);
+        }
+        (normalized, inv_stds)
+    }
+
+    fn affine(&self, normalized: &Matrix) -> Matrix {
+        let mut output = normalized.clone();
+        for row in output.as_mut_slice().chunks_mut(normalized.cols()) {
+            for ((x, g), b) in row.iter_mut().zip(self.gamma.iter()).zip(self.beta.iter()) {
+                *x = *x * g + b;
+            }
+        }
+        output
+    }
+}
+
+impl Layer for LayerNorm {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        let (normalized, inv_std) = self.normalize(input);
+        let output = self.affine(&normalized);
+        self.normalized = normalized;
+        self.inv_std = inv_std;
+        output
+    }
+
+    fn backward(&mut self, gradient: &mut Self, output_grad: &Matrix) -> Matrix {
+        let cols = output_grad.cols();
+
+        let mut scaled = output_grad.clone();
+        scaled.mul_from(&self.normalized);
+        gradient.gamma = scaled.sum_rows();
+        gradient.beta = output_grad.sum_rows();
+
+        let mut input_grad = output_grad.clone();
+        let rows = input_grad.as_mut_slice().chunks_mut(cols);
+        let normalized = self.normalized.chunks(cols);
+        for ((row, xhat), inv_std) in rows.zip(normalized).zip(self.inv_std.iter()) {
+            row.iter_mut()
+                .zip(self.gamma.iter())
+                .for_each(|(d, g)| *d *= g);
+            let sum = row.iter().sum::<f32>();
+            let dot = row.iter().zip(xhat).map(|(d, x)| d * x).sum::<f32>();
+            for (d, x) in row.iter_mut().zip(xhat) {
+                *d = inv_std * (*d - (sum + x * dot) / cols as f32);
+            }
+        }
+
+        input_grad
+    }
+
+    fn learn(&mut self, gradient: &mut Self, rate: &f32) {
+        self.gamma.add_scaled_from(&gradient.gamma, -rate);
+        self.beta.add_scaled_from(&gradient.beta, -rate);
+    }
+}
+
+/// Normalizes every feature (column) over the batch, followed by a learned
+/// scale (`gamma`) and shift (`beta`).
+///
+/// Training mode uses the batch statistics and folds them into running
+/// averages with the given `momentum`; eval mode uses the running averages.
+#[derive(Clone, Debug)]
+pub struct BatchNorm1d {
+    gamma: Matrix,
+    beta: Matrix,
+    running_mean: Matrix,
+    running_var: Matrix,
+    momentum: f32,
+    eps: f32,
+    mode: Mode,
+    normalized: Matrix,
+    inv_std: Vec<f32>,
+}
+
+impl BatchNorm1d {
+    pub fn new(features: usize) -> Self {
+        Self {
+            gamma: Matrix::from_iter(1, features, std::iter::repeat(1.0)),
+            beta: Matrix::new(1, features),
+            running_mean: Matrix::new(1, features),
+            running_var: Matrix::from_iter(1, features, std::iter::repeat(1.0)),
+            momentum: 0.1,
+            eps: 1e-5,
+            mode: Mode::Train,
+            normalized: Matrix::new(0, features),
+            inv_std: Vec::new(),
+        }
+    }
+
+    pub fn with_momentum(mut self, momentum: f32) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    pub fn gamma(&self) -> &Matrix {
+        &self.gamma
+    }
+
+    pub fn beta(&self) -> &Matrix {
+        &self.beta
+    }
+
+    pub fn running_mean(&self) -> &Matrix {
+        &self.running_mean
+    }
+
+    pub fn running_var(&self) -> &Matrix {
+        &self.running_var
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /// Same as `forward` in eval mode, without keeping anything for
+    /// `backward`.
+    pub fn infer(&self, input: &Matrix) -> Matrix {
+        let (normalized, _) = self.normalize(input, &self.running_mean, &self.running_var);
+        self.affine(&normalized)
+    }
+
+    fn normalize(&self, input: &Matrix, mean: &Matrix, var: &Matrix) -> (Matrix, Vec<f32>) {
+        let inv_std = var
+            .iter()
+            .map(|v| 1.0 / (v + self.eps).sqrt())
+            .collect::<Vec<_>>();
+        let mut normalized = input.clone();
+        for row in normalized.as_mut_slice().chunks_mut(input.cols()) {
+            for ((x, m), s) in row.iter_mut().zip(mean.iter()).zip(inv_std.iter()) {
+                *x = (*x - m) * s;
+            }
+        }
+        (normalized, inv_std)
+    }
+
+    fn affine(&self, normalized: &Matrix) -> Matrix {
+        let mut output = normalized.clone();
+        output.mul_from(&broadcast(&self.gamma, normalized.rows()));
+        output.add_row_from(&self.beta);
+        output
+    }
+}
+
+impl Layer for BatchNorm1d {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        let (rows, cols) = (input.rows(), input.cols());
+        let (mean, var) = match self.mode {
+            Mode::Train => {
+                assert!(rows > 0);
+                let mut mean = input.sum_rows();
+                mean.scale(1.0 / rows as f32);
+                let mut var = Matrix::new(1, cols);
+                for row in input.chunks(cols) {
+                    for ((v, x), m) in var.as_mut_slice().iter_mut().zip(row).zip(mean.iter()) {
+                        *v += (x - m) * (x - m);
+                    }
+                }
+                var.scale(1.0 / rows as f32);
+
+                let unbiased = rows as f32 / (rows.max(2) - 1) as f32;
+                self.running_mean.scale(1.0 - self.momentum);
+                self.running_mean.add_scaled_from(&mean, self.momentum);
+                self.running_var.scale(1.0 - self.momentum);
+                self.running_var
+                    .add_scaled_from(&var, self.momentum * unbiased);
+                (mean, var)
+            }
+            Mode::Eval => (self.running_mean.clone(), self.running_var.clone()),
+        };
+
+        let (normalized, inv_std) = self.normalize(input, &mean, &var);
+        let output = self.affine(&normalized);
+        self.normalized = normalized;
+        self.inv_std = inv_std;
+        output
+    }
+
+    fn backward(&mut self, gradient: &mut Self, output_grad: &Matrix) -> Matrix {
+        let (rows, cols) = (output_grad.rows(), output_grad.cols());
+
+        let mut scaled = output_grad.clone();
+        scaled.mul_from(&self.normalized);
+        gradient.gamma = scaled.sum_rows();
+        gradient.beta = output_grad.sum_rows();
+
+        let mut input_grad = output_grad.clone();
+        input_grad.mul_from(&broadcast(&self.gamma, rows));
+        if self.mode == Mode::Eval {
+            input_grad.mul_from(&broadcast(
+                &Matrix::from_iter(1, cols, self.inv_std.clone()),
+                rows,
+            ));
+            return input_grad;
+        }
+
+        let sum = input_grad.sum_rows();
+        let mut product = input_grad.clone();
+        product.mul_from(&self.normalized);
+        let dot = product.sum_rows();
+
+        let n = rows as f32;
+        let data = input_grad.as_mut_slice().chunks_mut(cols);
+        for (row, xhat) in data.zip(self.normalized.chunks(cols)) {
+            for col in 0..cols {
+                row[col] = self.inv_std[col] * (row[col] - (sum[col] + xhat[col] * dot[col]) / n);
+            }
+        }
+        input_grad
+    }
+
+    fn learn(&mut self, gradient: &mut Self, rate: &f32) {
+        self.gamma.add_scaled_from(&gradient.gamma, -rate);
+        self.beta.add_scaled_from(&gradient.beta, -rate);
+    }
+}
+
+/// A normalization layer that can be placed inside a `NeuralNetwork`.
+#[derive(Clone, Debug)]
+pub enum Norm {
+    Batch(BatchNorm1d),
+    Layer(LayerNorm),
+}
+
+impl Norm {
+    pub fn features(&self) -> usize {
+        match self {
+            Norm::Batch(norm) => norm.gamma().cols(),
+            Norm::Layer(norm) => norm.gamma().cols(),
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        if let Norm::Batch(norm) = self {
+            norm.set_mode(mode);
+        }
+    }
+
+    /// Same as `forward` in eval mode, without keeping anything for
+    /// `backward`.
+    pub fn infer(&self, input: &Matrix) -> Matrix {
+        match self {
+            Norm::Batch(norm) => norm.infer(input),
+            Norm::Layer(norm) => norm.infer(input),
+        }
+    }
+
+    /// The learned scale and shift, named `weight` and `bias`, followed by
+    /// the running statistics of a batch norm if `buffers` is set.
+    pub(crate) fn named(&self, buffers: bool) -> Vec<(&'static str, &Matrix)> {
+        match self {
+            Norm::Batch(norm) => {
+                let mut named = vec![("weight", &norm.gamma), ("bias", &norm.beta)];
+                if buffers {
+                    named.push(("running_mean", &norm.running_mean));
+                    named.push(("running_var", &norm.running_var));
+                }
+                named
+            }
+            Norm::Layer(norm) => vec![("weight", &norm.gamma), ("bias", &norm.beta)],
+        }
+    }
+
+    pub(crate) fn named_mut(&mut self, buffers: bool) -> Vec<(&'static str, &mut Matrix)> {
+        match self {
+            Norm::Batch(norm) => {
+                let mut named = vec![("weight", &mut norm.gamma), ("bias", &mut norm.beta)];
+                if buffers {
+                    named.push(("running_mean", &mut norm.running_mean));
+                    named.push(("running_var", &mut norm.running_var));
+                }
+                named
+            }
+            Norm::Layer(norm) => vec![("weight", &mut norm.gamma), ("bias", &mut norm.beta)],
+        }
+    }
+}
+
+impl Layer for Norm {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        match self {
+            Norm::Batch(norm) => norm.forward(input),
+            Norm::Layer(norm) => norm.forward(input),
+        }
+    }
+
+    fn backward(&mut self, gradient: &mut Self, output_grad: &Matrix) -> Matrix {
+        match (self, gradient) {
+            (Norm::Batch(norm), Norm::Batch(gradient)) => norm.backward(gradient, output_grad),
+            (Norm::Layer(norm), Norm::Layer(gradient)) => norm.backward(gradient, output_grad),
+            _ => panic!("gradient normalization does not match"),
+        }
+    }
+
+    fn learn(&mut self, gradient: &mut Self, rate: &f32) {
+        match (self, gradient) {
+            (Norm::Batch(norm), Norm::Batch(gradient)) => norm.learn(gradient, rate),
+            (Norm::Layer(norm), Norm::Layer(gradient)) => norm.learn(gradient, rate),
+            _ => panic!("gradient normalization does not match"),
+        }
+    }
+}
+
+fn broadcast(row: &Matrix, rows: usize) -> Matrix {
+    let mut result = Matrix::new(rows, row.cols());
+    result.add_row_from(row);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::{assert_close, numeric_grad};
+
+    #[test]
+    fn test_layer_norm_forward() {
+        let mut norm = LayerNorm::new(4);
+        let output = norm.forward(&Matrix::from_iter(1, 4, vec![1.0, 2.0, 3.0, 4.0]));
+
+        let mean = output.iter().sum::<f32>() / 4.0;
+        let var = output.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / 4.0;
+        assert!(mean.abs() < 1e-6);
+        assert!((var - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_layer_norm_backward() {
+        let mut norm = LayerNorm::new(3);
+        norm.gamma = Matrix::from_iter(1, 3, vec![0.5, 1.5, -1.0]);
+        norm.beta = Matrix::from_iter(1, 3, vec![0.1, 0.2, 0.3]);
+
+        let input = Matrix::from_iter(2, 3, vec![0.3, -1.2, 2.0, 1.0, 0.5, -0.7]);
+        let weights = Matrix::from_iter(2, 3, vec![1.0, -2.0, 0.5, 0.3, 1.2, -0.4]);
+        let loss = |norm: &mut LayerNorm, x: &Matrix| {
+            let mut y = norm.forward(x);
+            y.mul_from(&weights);
+            y.iter().sum::<f32>()
+        };
+
+        norm.forward(&input);
+        let mut gradient = norm.clone();
+        let input_grad = norm.backward(&mut gradient, &weights);
+
+        let expected = numeric_grad(&input, |x| loss(&mut norm.clone(), x));
+        assert_close(&input_grad, &expected, 1e-2);
+
+        let expected = numeric_grad(&norm.gamma, |g| {
+            let mut probe = norm.clone();
+            probe.gamma = g.clone();
+            loss(&mut probe, &input)
+        });
+        assert_close(&gradient.gamma, &expected, 1e-2);
+    }
+
+    #[test]
+    fn test_batch_norm_train_eval() {
+        let mut norm = BatchNorm1d::new(2).with_momentum(1.0);
+        let input = Matrix::from_iter(3, 2, vec![1.0, 10.0, 2.0, 20.0, 3.0, 30.0]);
+        let output = norm.forward(&input);
+
+        for col in 0..2 {
+            let column = output.get_col(col).unwrap().copied().collect::<Vec<_>>();
+            assert!(column.iter().sum::<f32>().abs() < 1e-5);
+            assert!((column[2] - 1.2247).abs() < 1e-3);
+        }
+        assert_eq!(
+            norm.running_mean(),
+            &Matrix::from_iter(1, 2, vec![2.0, 20.0])
+        );
+        assert_close(
+            norm.running_var(),
+            &Matrix::from_iter(1, 2, vec![1.0, 100.0]),
+            1e-6,
+        );
+
+        norm.set_mode(Mode::Eval);
+        let output = norm.forward(&Matrix::from_iter(1, 2, vec![3.0, 30.0]));
+        assert!((output[0] - 1.0).abs() < 1e-4);
+        assert!((output[1] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_batch_norm_backward() {
+        let mut norm = BatchNorm1d::new(2);
+        norm.gamma = Matrix::from_iter(1, 2, vec![0.5, -1.5]);
+        norm.beta = Matrix::from_iter(1, 2, vec![0.1, 0.2]);
+
+        let input = Matrix::from_iter(3, 2, vec![0.3, -1.2, 2.0, 1.0, 0.5, -0.7]);
+        let weights = Matrix::from_iter(3, 2, vec![1.0, -2.0, 0.5, 0.3, 1.2, -0.4]);
+        let loss = |norm: &mut BatchNorm1d, x: &Matrix| {
+            let mut y = norm.forward(x);
+            y.mul_from(&weights);
+            y.iter().sum::<f32>()
+        };
+
+        norm.forward(&input);
+        let mut gradient = norm.clone();
+        let input_grad = norm.backward(&mut gradient, &weights);
+
+        let expected = numeric_grad(&input, |x| loss(&mut norm.clone(), x));
+        assert_close(&input_grad, &expected, 1e-2);
+
+        let expected = numeric_grad(&norm.gamma, |g| {
+            let mut probe = norm.clone();
+            probe.gamma = g.clone();
+            loss(&mut probe, &input)
+        });
+        assert_close(&gradient.gamma, &expected, 1e-2);
+    }
+}
@@ -0,0 +1,168 @@
+use super::{Layer, LayerNorm, Linear, MultiHeadAttention};
+use crate::matrix::Matrix;
+
+/// Sinusoidal position encodings, one `1 x model` row per position.
+pub fn positional_encoding(positions: usize, model: usize) -> Matrix {
+    let mut encoding = Matrix::new(positions, model);
+    for pos in 0..positions {
+        for i in 0..model {
+            let angle = pos as f32 / 10000f32.powf((i - i % 2) as f32 / model as f32);
+            let value = if i % 2 == 0 { angle.sin() } else { angle.cos() };
+            encoding.set(pos, i, value);
+        }
+    }
+    encoding
+}
+
+/// Pre-norm transformer block:
+///
+/// ```text
+/// h = x + attention(norm1(x))
+/// y = h + feed_forward(norm2(h))
+/// ```
+///
+/// where the feed-forward network is `linear -> relu -> linear`.
+#[derive(Clone, Debug)]
+pub struct TransformerBlock {
+    norm1: LayerNorm,
+    attention: MultiHeadAttention,
+    norm2: LayerNorm,
+    hidden: Linear,
+    output: Linear,
+    hidden_input: Matrix,
+}
+
+impl TransformerBlock {
+    pub fn new(model: usize, heads: usize, hidden: usize, causal: bool) -> Self {
+        Self::from_iter(model, heads, hidden, causal, std::iter::repeat(0.0))
+    }
+
+    /// Fills the attention projections and then the feed-forward layers from a
+    /// single iterator. The normalization layers start as the identity.
+    pub fn from_iter<I>(model: usize, heads: usize, hidden: usize, causal: bool, iter: I) -> Self
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        let mut iter = iter.into_iter();
+        Self {
+            norm1: LayerNorm::new(model),
+            attention: MultiHeadAttention::from_iter(model, heads, causal, iter.by_ref()),
+            norm2: LayerNorm::new(model),
+            hidden: Linear::from_iter(model, hidden, iter.by_ref()),
+            output: Linear::from_iter(hidden, model, iter.by_ref()),
+            hidden_input: Matrix::new(0, hidden),
+        }
+    }
+
+    pub fn attention(&self) -> &MultiHeadAttention {
+        &self.attention
+    }
+}
+
+impl Layer for TransformerBlock {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        let normalized = self.norm1.forward(input);
+        let mut h = self.attention.forward(&normalized);
+        h.add_from(input);
+
+        let normalized = self.norm2.forward(&h);
+        self.hidden_input = self.hidden.forward(&normalized);
+        let mut activated = self.hidden_input.clone();
+        activated.relu();
+
+        let mut output = self.output.forward(&activated);
+        output.add_from(&h);
+        output
+    }
+
+    fn backward(&mut self, gradient: &mut Self, output_grad: &Matrix) -> Matrix {
+        let mut activated_grad = self.output.backward(&mut gradient.output, output_grad);
+        for (d, x) in activated_grad
+            .as_mut_slice()
+            .iter_mut()
+            .zip(self.hidden_input.iter())
+        {
+            if *x <= 0.0 {
+                *d = 0.0;
+            }
+        }
+        let normalized_grad = self.hidden.backward(&mut gradient.hidden, &activated_grad);
+
+        let mut h_grad = self.norm2.backward(&mut gradient.norm2, &normalized_grad);
+        h_grad.add_from(output_grad);
+
+        let normalized_grad = self.attention.backward(&mut gradient.attention, &h_grad);
+        let mut input_grad = self.norm1.backward(&mut gradient.norm1, &normalized_grad);
+        input_grad.add_from(&h_grad);
+        input_grad
+    }
+
+    fn learn(&mut self, gradient: &mut Self, rate: &f32) {
+        self.norm1.learn(&mut gradient.norm1, rate);
+        self.attention.learn(&mut gradient.attention, rate);
+        self.norm2.learn(&mut gradient.norm2, rate);
+        self.hidden.learn(&mut gradient.hidden, rate);
+        self.output.learn(&mut gradient.output, rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::{assert_close, numeric_grad};
+
+    #[test]
+    fn test_positional_encoding() {
+        let encoding = positional_encoding(3, 4);
+        assert_eq!(
+            encoding.get_row_matrix(0),
+            Some(Matrix::from_iter(1, 4, vec![0.0, 1.0, 0.0, 1.0]))
+        );
+        assert_eq!(encoding.get(1, 0), Some(&1f32.sin()));
+        assert_eq!(encoding.get(1, 3), Some(&0.01f32.cos()));
+    }
+
+    #[test]
+    fn test_transformer_block_backward() {
+        let weights = (0..).map(|i| ((i as f32) * 0.9).sin() * 0.4);
+        let mut block = TransformerBlock::from_iter(4, 2, 6, true, weights);
+        let input = Matrix::from_iter(3, 4, (0..).map(|i| (i as f32 * 0.37).cos()));
+        let output_grad = Matrix::from_iter(3, 4, (0..).map(|i| (i as f32 * 0.53).sin()));
+
+        block.forward(&input);
+        let mut gradient = block.clone();
+        let input_grad = block.backward(&mut gradient, &output_grad);
+
+        let expected = numeric_grad(&input, |x| {
+            let mut y = block.clone().forward(x);
+            y.mul_from(&output_grad);
+            y.iter().sum()
+        });
+        assert_close(&input_grad, &expected, 2e-2);
+    }
+
+    #[test]
+    fn test_transformer_block_learns() {
+        let weights = (0..).map(|i| ((i as f32) * 0.9).sin() * 0.4);
+        let mut block = TransformerBlock::from_iter(4, 2, 8, true, weights);
+        let input = &Matrix::from_iter(4, 4, (0..).map(|i| (i as f32 * 0.37).cos()))
+            + &positional_encoding(4, 4);
+        let target = Matrix::from_iter(4, 4, (0..).map(|i| (i % 3) as f32 - 1.0));
+
+        let loss = |block: &mut TransformerBlock| -> (f32, Matrix) {
+            let diff = &block.forward(&input) - &target;
+            (diff.iter().map(|d| d * d).sum(), diff)
+        };
+
+        let (before, _) = loss(&mut block);
+        let mut gradient = block.clone();
+        for _ in 0..50 {
+            let (_, mut diff) = loss(&mut block);
+            diff.scale(2.0);
+            block.backward(&mut gradient, &diff);
+            block.learn(&mut gradient, &0.01);
+        }
+        let (after, _) = loss(&mut block);
+        assert!(after < before * 0.5, "{before} -> {after}");
+    }
+}
@@ -1,5 +1,45 @@
+mod activation;
+mod anomaly;
+mod callback;
+mod checkpoint;
+mod clip;
+mod codegen;
+mod config;
+mod cross_validation;
+mod data;
+mod init;
+mod json;
+mod layer;
+mod loss;
 mod matrix;
+mod metrics;
 mod neural_network;
+mod onnx;
+mod optimizer;
+mod regularizer;
+mod safetensors;
+mod scheduler;
+mod trainer;
+mod zip;
 
+pub use crate::activation::*;
+pub use crate::anomaly::Anomaly;
+pub use crate::callback::*;
+pub use crate::checkpoint::CheckpointError;
+pub use crate::clip::*;
+pub use crate::config::*;
+pub use crate::cross_validation::*;
+pub use crate::data::*;
+pub use crate::init::*;
+pub use crate::json::*;
+pub use crate::layer::*;
+pub use crate::loss::*;
 pub use crate::matrix::*;
+pub use crate::metrics::*;
 pub use crate::neural_network::*;
+pub use crate::onnx::OnnxError;
+pub use crate::optimizer::*;
+pub use crate::regularizer::*;
+pub use crate::safetensors::SafetensorsError;
+pub use crate::scheduler::*;
+pub use crate::trainer::*;
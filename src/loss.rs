@@ -0,0 +1,148 @@
+use crate::matrix::Matrix;
+use std::fmt;
+use std::str::FromStr;
+
+/// The cost a network is trained to minimize, summed over the outputs of a
+/// row and averaged over the rows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Loss {
+    /// Squared error.
+    #[default]
+    Mse,
+    /// Binary cross-entropy of outputs in `(0, 1)`, e.g. after a sigmoid.
+    CrossEntropy,
+    /// Categorical cross-entropy of the softmax of each row, for outputs that
+    /// are logits, i.e. an identity output activation. Computed through
+    /// log-softmax, so it stays finite for logits of any size.
+    SoftmaxCrossEntropy,
+}
+
+/// Keeps `ln` and its derivative finite for saturated outputs.
+const EPS: f32 = 1e-7;
+
+impl Loss {
+    pub fn cost(&self, output: &Matrix, target: &Matrix) -> f32 {
+        assert_eq!(output.rows(), target.rows());
+        assert_eq!(output.cols(), target.cols());
+
+        if let Loss::SoftmaxCrossEntropy = self {
+            let mut log_softmax = output.clone();
+            log_softmax.log_softmax_rows();
+            let result = log_softmax
+                .iter()
+                .zip(target.iter())
+                .map(|(a, b)| -b * a)
+                .sum::<f32>();
+            return result / output.rows() as f32;
+        }
+
+        let mut result = 0.0;
+        for (a, b) in output.iter().zip(target.iter()) {
+            result += match self {
+                Loss::Mse => (a - b) * (a - b),
+                Loss::CrossEntropy => {
+                    let a = a.clamp(EPS, 1.0 - EPS);
+                    -(b * a.ln() + (1.0 - b) * (1.0 - a).ln())
+                }
+                Loss::SoftmaxCrossEntropy => unreachable!(),
+            };
+        }
+        result / output.rows() as f32
+    }
+
+    /// The derivative of `cost` with respect to `output`. For softmax
+    /// cross-entropy this is `(softmax(output) - target) / n`, assuming every
+    /// target row sums to 1.
+    pub fn gradient(&self, output: &Matrix, target: &Matrix) -> Matrix {
+        assert_eq!(output.rows(), target.rows());
+        assert_eq!(output.cols(), target.cols());
+
+        let n = output.rows() as f32;
+        if let Loss::SoftmaxCrossEntropy = self {
+            let mut gradient = output.clone();
+            gradient.softmax_rows();
+            gradient -= target;
+            gradient.scale(1.0 / n);
+            return gradient;
+        }
+
+        let mut gradient = output - target;
+        match self {
+            Loss::Mse => gradient.scale(2.0 / n),
+            Loss::CrossEntropy => {
+                for (g, a) in gradient.as_mut_slice().iter_mut().zip(output.iter()) {
+                    let a = a.clamp(EPS, 1.0 - EPS);
+                    *g /= a * (1.0 - a) * n;
+                }
+            }
+            Loss::SoftmaxCrossEntropy => unreachable!(),
+        }
+        gradient
+    }
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Loss::Mse => "mse",
+            Loss::CrossEntropy => "cross_entropy",
+            Loss::SoftmaxCrossEntropy => "softmax_cross_entropy",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Loss {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mse" => Ok(Loss::Mse),
+            "cross_entropy" => Ok(Loss::CrossEntropy),
+            "softmax_cross_entropy" => Ok(Loss::SoftmaxCrossEntropy),
+            _ => Err(format!("unknown loss `{s}`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::{assert_close, numeric_grad};
+
+    #[test]
+    fn test_gradient() {
+        let output = Matrix::from_iter(2, 2, vec![0.2, 0.7, 0.9, 0.4]);
+        let target = Matrix::from_iter(2, 2, vec![0.0, 1.0, 1.0, 0.0]);
+        for loss in [Loss::Mse, Loss::CrossEntropy] {
+            let expected = numeric_grad(&output, |x| loss.cost(x, &target));
+            assert_close(&loss.gradient(&output, &target), &expected, 1e-2);
+        }
+        assert!((Loss::CrossEntropy.cost(&target, &target)).abs() < 1e-5);
+
+        let loss = Loss::SoftmaxCrossEntropy;
+        let expected = numeric_grad(&output, |x| loss.cost(x, &target));
+        assert_close(&loss.gradient(&output, &target), &expected, 1e-2);
+    }
+
+    #[test]
+    fn test_softmax_cross_entropy_extremes() {
+        let output = Matrix::from_iter(2, 2, vec![1000.0, -1000.0, -1000.0, 1000.0]);
+        let target = Matrix::from_iter(2, 2, vec![0.0, 1.0, 0.0, 1.0]);
+        let loss = Loss::SoftmaxCrossEntropy;
+        assert_eq!(loss.cost(&output, &target), 1000.0);
+        let gradient = loss.gradient(&output, &target);
+        assert_eq!(
+            gradient.iter().copied().collect::<Vec<_>>(),
+            vec![0.5, -0.5, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        for loss in [Loss::Mse, Loss::CrossEntropy, Loss::SoftmaxCrossEntropy] {
+            assert_eq!(loss.to_string().parse(), Ok(loss));
+        }
+        assert!("hinge".parse::<Loss>().is_err());
+    }
+}
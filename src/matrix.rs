@@ -1,4 +1,11 @@
+mod npy;
 mod ops;
+mod random;
+mod softmax;
+
+pub use self::npy::{read_npz, write_npz, NpyError};
+pub use self::random::random_permutation;
+pub use self::softmax::sigmoid;
 use std::ops::Deref;
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
@@ -85,7 +92,7 @@ impl Matrix {
 
     pub fn sigmoid(&mut self) {
         for i in 0..self.data.len() {
-            self.data[i] = 1.0 / (1.0 + (-self.data[i]).exp());
+            self.data[i] = sigmoid(self.data[i]);
         }
     }
 
@@ -129,6 +136,149 @@ impl Matrix {
             }
         }
     }
+
+    pub fn as_mut_slice(&mut self) -> &mut [f32] {
+        &mut self.data
+    }
+
+    pub fn apply<F>(&mut self, f: F)
+    where
+        F: Fn(f32) -> f32,
+    {
+        for x in self.data.iter_mut() {
+            *x = f(*x);
+        }
+    }
+
+    pub fn map<F>(&self, f: F) -> Self
+    where
+        F: Fn(f32) -> f32,
+    {
+        Self {
+            data: self.data.iter().map(|&x| f(x)).collect(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    pub fn scale(&mut self, factor: f32) {
+        self.apply(|x| x * factor);
+    }
+
+    /// `self += factor * other`, element-wise.
+    pub fn add_scaled_from(&mut self, other: &Self, factor: f32) {
+        assert_eq!(self.rows, other.rows);
+        assert_eq!(self.cols, other.cols);
+        for i in 0..self.data.len() {
+            self.data[i] += factor * other.data[i];
+        }
+    }
+
+    /// Element-wise (Hadamard) product.
+    pub fn mul_from(&mut self, other: &Self) {
+        assert_eq!(self.rows, other.rows);
+        assert_eq!(self.cols, other.cols);
+        for i in 0..self.data.len() {
+            self.data[i] *= other.data[i];
+        }
+    }
+
+    /// Adds a `1 x cols` row to every row of `self`.
+    pub fn add_row_from(&mut self, row: &Self) {
+        assert_eq!(row.rows, 1);
+        assert_eq!(self.cols, row.cols);
+        for chunk in self.data.chunks_mut(self.cols) {
+            for (a, b) in chunk.iter_mut().zip(row.data.iter()) {
+                *a += b;
+            }
+        }
+    }
+
+    /// Sums over the rows, returning a `1 x cols` matrix.
+    pub fn sum_rows(&self) -> Self {
+        let mut result = Self::new(1, self.cols);
+        for chunk in self.data.chunks(self.cols) {
+            for (a, b) in result.data.iter_mut().zip(chunk.iter()) {
+                *a += b;
+            }
+        }
+        result
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = Self::new(self.cols, self.rows);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                result.data[col * self.rows + row] = self.data[row * self.cols + col];
+            }
+        }
+        result
+    }
+
+    /// Copies the rows `start..end` into a new matrix.
+    pub fn slice_rows(&self, start: usize, end: usize) -> Self {
+        assert!(start <= end && end <= self.rows);
+        Self {
+            data: self.data[start * self.cols..end * self.cols].to_vec(),
+            rows: end - start,
+            cols: self.cols,
+        }
+    }
+
+    /// Builds a matrix from the given rows of `self`, in order.
+    pub fn select_rows(&self, rows: &[usize]) -> Self {
+        let mut data = Vec::with_capacity(rows.len() * self.cols);
+        for &row in rows {
+            assert!(row < self.rows);
+            data.extend_from_slice(&self.data[row * self.cols..(row + 1) * self.cols]);
+        }
+        Self {
+            data,
+            rows: rows.len(),
+            cols: self.cols,
+        }
+    }
+
+    /// The column index of the largest value in every row.
+    pub fn argmax_rows(&self) -> Vec<usize> {
+        self.data
+            .chunks(self.cols)
+            .map(|row| {
+                let mut best = 0;
+                for (i, x) in row.iter().enumerate() {
+                    if *x > row[best] {
+                        best = i;
+                    }
+                }
+                best
+            })
+            .collect()
+    }
+
+    /// Copies the columns `start..end` into a new matrix.
+    pub fn slice_cols(&self, start: usize, end: usize) -> Self {
+        assert!(start <= end && end <= self.cols);
+        let mut data = Vec::with_capacity(self.rows * (end - start));
+        for chunk in self.data.chunks(self.cols) {
+            data.extend_from_slice(&chunk[start..end]);
+        }
+        Self {
+            data,
+            rows: self.rows,
+            cols: end - start,
+        }
+    }
+
+    /// Overwrites the columns starting at `start` with `other`.
+    pub fn set_cols(&mut self, start: usize, other: &Self) {
+        assert_eq!(self.rows, other.rows);
+        assert!(start + other.cols <= self.cols);
+        for row in 0..self.rows {
+            let dst = row * self.cols + start;
+            let src = row * other.cols;
+            self.data[dst..dst + other.cols].copy_from_slice(&other.data[src..src + other.cols]);
+        }
+    }
 }
 
 impl Deref for Matrix {
@@ -254,4 +404,45 @@ mod tests {
         assert_eq!(m.get(1, 0), Some(&0.8807970779778823));
         assert_eq!(m.get(1, 1), Some(&0.9525741268224334));
     }
+
+    #[test]
+    fn test_transpose() {
+        let m = Matrix::from_iter(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(
+            m.transpose(),
+            Matrix::from_iter(3, 2, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0])
+        );
+    }
+
+    #[test]
+    fn test_add_row_from_sum_rows() {
+        let mut m = Matrix::from_iter(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        m.add_row_from(&Matrix::from_iter(1, 2, vec![10.0, 20.0]));
+        assert_eq!(m, Matrix::from_iter(2, 2, vec![11.0, 22.0, 13.0, 24.0]));
+        assert_eq!(m.sum_rows(), Matrix::from_iter(1, 2, vec![24.0, 46.0]));
+    }
+
+    #[test]
+    fn test_slice_rows_argmax() {
+        let m = Matrix::from_iter(3, 2, vec![1.0, 2.0, 4.0, 3.0, 5.0, 5.0]);
+        assert_eq!(
+            m.slice_rows(1, 3),
+            Matrix::from_iter(2, 2, vec![4.0, 3.0, 5.0, 5.0])
+        );
+        assert_eq!(m.argmax_rows(), vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn test_slice_set_cols() {
+        let m = Matrix::from_iter(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let s = m.slice_cols(1, 3);
+        assert_eq!(s, Matrix::from_iter(2, 2, vec![2.0, 3.0, 5.0, 6.0]));
+
+        let mut z = Matrix::new(2, 3);
+        z.set_cols(1, &s);
+        assert_eq!(
+            z,
+            Matrix::from_iter(2, 3, vec![0.0, 2.0, 3.0, 0.0, 5.0, 6.0])
+        );
+    }
 }
@@ -0,0 +1,329 @@
+use super::Matrix;
+use std::fmt;
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+#[derive(Debug)]
+pub enum NpyError {
+    Io(std::io::Error),
+    Magic,
+    Version(u8),
+    /// The header dict is missing a key or could not be parsed.
+    Header(String),
+    /// Only `f4` and `f8` arrays are supported.
+    DataType(String),
+    Truncated {
+        expected: usize,
+        found: usize,
+    },
+    Zip(String),
+}
+
+impl fmt::Display for NpyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NpyError::Io(err) => write!(f, "{err}"),
+            NpyError::Magic => write!(f, "invalid NPY magic"),
+            NpyError::Version(major) => write!(f, "unsupported NPY version {major}"),
+            NpyError::Header(msg) => write!(f, "invalid NPY header: {msg}"),
+            NpyError::DataType(descr) => write!(f, "unsupported NPY dtype '{descr}'"),
+            NpyError::Truncated { expected, found } => {
+                write!(f, "expected {expected} bytes, found {found}")
+            }
+            NpyError::Zip(msg) => write!(f, "invalid NPZ archive: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for NpyError {}
+
+impl From<std::io::Error> for NpyError {
+    fn from(err: std::io::Error) -> Self {
+        NpyError::Io(err)
+    }
+}
+
+impl Matrix {
+    /// Reads a `.npy` file, see [`Matrix::from_npy`].
+    pub fn read_npy<P: AsRef<Path>>(path: P) -> Result<Self, NpyError> {
+        Self::from_npy(&std::fs::read(path)?)
+    }
+
+    /// Writes `self` as a `.npy` file, see [`Matrix::to_npy`].
+    pub fn write_npy<P: AsRef<Path>>(&self, path: P) -> Result<(), NpyError> {
+        Ok(std::fs::write(path, self.to_npy())?)
+    }
+
+    /// Parses an array in NumPy's `.npy` format (versions 1 to 3).
+    ///
+    /// `f4` and `f8` in either byte order are supported, Fortran ordered data
+    /// is transposed into row-major order. A 0-d array becomes `1 x 1`, a 1-d
+    /// array of length `n` becomes `n x 1`, and for three or more dimensions
+    /// the trailing ones are flattened into the columns.
+    pub fn from_npy(bytes: &[u8]) -> Result<Self, NpyError> {
+        if take(bytes, 0, MAGIC.len())? != MAGIC {
+            return Err(NpyError::Magic);
+        }
+        let major = take(bytes, 6, 1)?[0];
+        let (len, start) = match major {
+            1 => {
+                let b = take(bytes, 8, 2)?;
+                (u16::from_le_bytes([b[0], b[1]]) as usize, 10)
+            }
+            2 | 3 => {
+                let b = take(bytes, 8, 4)?;
+                (u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize, 12)
+            }
+            major => return Err(NpyError::Version(major)),
+        };
+        let header = String::from_utf8_lossy(take(bytes, start, len)?);
+        let (descr, fortran_order, shape) = parse_header(&header)?;
+
+        let (little, size) = match descr.as_str() {
+            "<f4" | "=f4" => (true, 4),
+            ">f4" => (false, 4),
+            "<f8" | "=f8" => (true, 8),
+            ">f8" => (false, 8),
+            _ => return Err(NpyError::DataType(descr)),
+        };
+
+        if fortran_order && shape.len() > 2 {
+            return Err(NpyError::Header(
+                "Fortran order is only supported up to two dimensions".to_string(),
+            ));
+        }
+        // A Fortran ordered matrix reads as the C ordered transpose.
+        let mut dims = shape;
+        if fortran_order {
+            dims.reverse();
+        }
+        let rows = dims.first().copied().unwrap_or(1);
+        let cols = dims
+            .iter()
+            .skip(1)
+            .try_fold(1usize, |n, &dim| n.checked_mul(dim));
+        let Some((cols, data_len)) =
+            cols.and_then(|cols| Some((cols, rows.checked_mul(cols)?.checked_mul(size)?)))
+        else {
+            return Err(NpyError::Header("shape overflows".to_string()));
+        };
+
+        let data = take(bytes, start + len, data_len)?;
+        let values = data.chunks(size).map(|b| match (size, little) {
+            (4, true) => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            (4, false) => f32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+            (_, true) => {
+                f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+            }
+            (_, false) => {
+                f64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+            }
+        });
+        let matrix = Matrix::from_iter(rows, cols, values.collect::<Vec<_>>());
+
+        Ok(match fortran_order && dims.len() == 2 {
+            true => matrix.transpose(),
+            false => matrix,
+        })
+    }
+
+    /// Serializes `self` as a version 1 `.npy` array of little-endian `f32`
+    /// with shape `(rows, cols)`.
+    pub fn to_npy(&self) -> Vec<u8> {
+        let mut header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.rows, self.cols
+        );
+        // The magic, version, length and header are padded to a multiple of
+        // 64 bytes and terminated by a newline.
+        let unpadded = MAGIC.len() + 4 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+        header.push('\n');
+
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + header.len() + 4 * self.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for x in self.iter() {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Reads every array of a `.npz` archive, as written by `numpy.savez` or
+/// `numpy.savez_compressed`, keyed by name without the `.npy` extension.
+pub fn read_npz<P: AsRef<Path>>(path: P) -> Result<Vec<(String, Matrix)>, NpyError> {
+    let entries = crate::zip::read_zip(&std::fs::read(path)?).map_err(NpyError::Zip)?;
+    entries
+        .into_iter()
+        .map(|(name, data)| {
+            let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+            Ok((name, Matrix::from_npy(&data)?))
+        })
+        .collect()
+}
+
+/// Writes the arrays into an uncompressed `.npz` archive that `numpy.load`
+/// returns keyed by the given names.
+pub fn write_npz<P: AsRef<Path>>(path: P, arrays: &[(&str, &Matrix)]) -> Result<(), NpyError> {
+    let entries = arrays
+        .iter()
+        .map(|(name, matrix)| (format!("{name}.npy"), matrix.to_npy()))
+        .collect::<Vec<_>>();
+    Ok(std::fs::write(path, crate::zip::write_zip(&entries))?)
+}
+
+/// Pulls `descr`, `fortran_order` and `shape` out of the Python dict literal
+/// in the header, e.g. `{'descr': '<f8', 'fortran_order': False, 'shape': (3,), }`.
+fn parse_header(header: &str) -> Result<(String, bool, Vec<usize>), NpyError> {
+    let value = |key: &str| {
+        ["'", "\""]
+            .iter()
+            .find_map(|quote| {
+                header
+                    .find(&format!("{quote}{key}{quote}"))
+                    .map(|i| i + key.len() + 2)
+            })
+            .and_then(|i| header[i..].trim_start().strip_prefix(':'))
+            .map(str::trim_start)
+            .ok_or_else(|| NpyError::Header(format!("missing '{key}'")))
+    };
+
+    let descr = value("descr")?;
+    let descr = descr
+        .strip_prefix(['\'', '"'])
+        .and_then(|s| s.split(['\'', '"']).next())
+        .ok_or_else(|| NpyError::Header("'descr' is not a string".to_string()))?
+        .to_string();
+
+    let fortran_order = match value("fortran_order")? {
+        s if s.starts_with("True") => true,
+        s if s.starts_with("False") => false,
+        _ => {
+            return Err(NpyError::Header(
+                "'fortran_order' is not a bool".to_string(),
+            ))
+        }
+    };
+
+    let shape = value("shape")?
+        .strip_prefix('(')
+        .and_then(|s| s.split(')').next())
+        .ok_or_else(|| NpyError::Header("'shape' is not a tuple".to_string()))?
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.trim_end_matches('L')
+                .parse()
+                .map_err(|_| NpyError::Header(format!("invalid dimension '{s}'")))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    Ok((descr, fortran_order, shape))
+}
+
+fn take(bytes: &[u8], start: usize, len: usize) -> Result<&[u8], NpyError> {
+    let end = start
+        .checked_add(len)
+        .ok_or_else(|| NpyError::Header("length overflows".to_string()))?;
+    bytes.get(start..end).ok_or(NpyError::Truncated {
+        expected: end,
+        found: bytes.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn npy(header: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_npy_round_trip() {
+        let m = Matrix::from_iter(2, 3, vec![1.0, -2.5, 3.0, 0.0, 1e-7, 42.0]);
+        let bytes = m.to_npy();
+        let data = bytes.len() - 4 * 6;
+        assert_eq!(data % 64, 0);
+        assert_eq!(bytes[data - 1], b'\n');
+        assert_eq!(Matrix::from_npy(&bytes).unwrap(), m);
+    }
+
+    #[test]
+    fn test_from_npy_dtypes_and_order() {
+        let data = [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }\n";
+        let c = Matrix::from_npy(&npy(header, &data)).unwrap();
+        assert_eq!(
+            c,
+            Matrix::from_iter(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
+        );
+
+        let header = "{'descr': '<f8', 'fortran_order': True, 'shape': (2, 3), }\n";
+        let f = Matrix::from_npy(&npy(header, &data)).unwrap();
+        assert_eq!(
+            f,
+            Matrix::from_iter(2, 3, vec![1.0, 3.0, 5.0, 2.0, 4.0, 6.0])
+        );
+
+        let data = [1.5f32, -1.0]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect::<Vec<_>>();
+        let header = "{'descr': '>f4', 'fortran_order': False, 'shape': (2,), }\n";
+        let v = Matrix::from_npy(&npy(header, &data)).unwrap();
+        assert_eq!(v, Matrix::from_iter(2, 1, vec![1.5, -1.0]));
+    }
+
+    #[test]
+    fn test_from_npy_errors() {
+        assert!(matches!(
+            Matrix::from_npy(b"NUMPY\x01\x00"),
+            Err(NpyError::Magic)
+        ));
+
+        let header = "{'descr': '<i8', 'fortran_order': False, 'shape': (1,), }\n";
+        let err = Matrix::from_npy(&npy(header, &[0; 8])).unwrap_err();
+        assert_eq!(err.to_string(), "unsupported NPY dtype '<i8'");
+
+        let header = "{'descr': '<f4', 'fortran_order': False}\n";
+        let err = Matrix::from_npy(&npy(header, &[])).unwrap_err();
+        assert_eq!(err.to_string(), "invalid NPY header: missing 'shape'");
+
+        let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 2), }\n";
+        let err = Matrix::from_npy(&npy(header, &[0; 12])).unwrap_err();
+        assert!(matches!(err, NpyError::Truncated { .. }));
+
+        let header = "{'descr': '<f4', 'fortran_order': False, \
+                      'shape': (4294967296, 4294967296, 4294967296), }\n";
+        let err = Matrix::from_npy(&npy(header, &[])).unwrap_err();
+        assert_eq!(err.to_string(), "invalid NPY header: shape overflows");
+    }
+
+    #[test]
+    fn test_npz_round_trip() {
+        let path = std::env::temp_dir().join("rustml_test_arrays.npz");
+        let weight = Matrix::from_iter(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let bias = Matrix::from_iter(1, 2, vec![-1.0, 0.5]);
+        write_npz(&path, &[("weight", &weight), ("bias", &bias)]).unwrap();
+
+        let arrays = read_npz(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            arrays,
+            vec![("weight".to_string(), weight), ("bias".to_string(), bias)]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use super::Matrix;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+impl Matrix {
+    /// Samples every entry uniformly from `[low, high]`.
+    pub fn random_uniform<R: Rng>(
+        rows: usize,
+        cols: usize,
+        low: f32,
+        high: f32,
+        rng: &mut R,
+    ) -> Self {
+        assert!(low <= high);
+        Self::from_iter(
+            rows,
+            cols,
+            std::iter::repeat_with(|| rng.gen_range(low..=high)),
+        )
+    }
+
+    /// Samples every entry from `N(mean, std^2)`.
+    pub fn random_normal<R: Rng>(
+        rows: usize,
+        cols: usize,
+        mean: f32,
+        std: f32,
+        rng: &mut R,
+    ) -> Self {
+        Self::from_iter(
+            rows,
+            cols,
+            std::iter::repeat_with(|| mean + std * standard_normal(rng)),
+        )
+    }
+
+    /// Samples from `N(mean, std^2)`, redrawing anything further than two
+    /// standard deviations from the mean.
+    pub fn random_truncated_normal<R: Rng>(
+        rows: usize,
+        cols: usize,
+        mean: f32,
+        std: f32,
+        rng: &mut R,
+    ) -> Self {
+        let sample = || loop {
+            let x = standard_normal(rng);
+            if x.abs() <= 2.0 {
+                return mean + std * x;
+            }
+        };
+        Self::from_iter(rows, cols, std::iter::repeat_with(sample))
+    }
+
+    /// Every entry is `1.0` with probability `p` and `0.0` otherwise.
+    pub fn random_bernoulli<R: Rng>(rows: usize, cols: usize, p: f32, rng: &mut R) -> Self {
+        assert!((0.0..=1.0).contains(&p));
+        Self::from_iter(
+            rows,
+            cols,
+            std::iter::repeat_with(|| match rng.gen::<f32>() < p {
+                true => 1.0,
+                false => 0.0,
+            }),
+        )
+    }
+
+    /// Shuffles the rows in place and returns the permutation that was
+    /// applied, so a row-aligned matrix can follow with `select_rows`.
+    pub fn shuffle_rows<R: Rng>(&mut self, rng: &mut R) -> Vec<usize> {
+        let permutation = random_permutation(self.rows, rng);
+        *self = self.select_rows(&permutation);
+        permutation
+    }
+}
+
+/// A uniformly random ordering of `0..n`.
+pub fn random_permutation<R: Rng>(n: usize, rng: &mut R) -> Vec<usize> {
+    let mut permutation = (0..n).collect::<Vec<_>>();
+    permutation.shuffle(rng);
+    permutation
+}
+
+fn standard_normal<R: Rng>(rng: &mut R) -> f32 {
+    // Box-Muller; `1 - u` keeps the logarithm finite.
+    let u: f32 = 1.0 - rng.gen::<f32>();
+    let v: f32 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f32::consts::PI * v).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn mean_std(m: &Matrix) -> (f32, f32) {
+        let n = m.len() as f32;
+        let mean = m.iter().sum::<f32>() / n;
+        let var = m.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / n;
+        (mean, var.sqrt())
+    }
+
+    #[test]
+    fn test_random_uniform() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let m = Matrix::random_uniform(50, 50, -2.0, 3.0, &mut rng);
+        assert!(m.iter().all(|&x| (-2.0..=3.0).contains(&x)));
+        let (mean, _) = mean_std(&m);
+        assert!((mean - 0.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_random_normal() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (mean, std) = mean_std(&Matrix::random_normal(100, 100, 1.0, 2.0, &mut rng));
+        assert!((mean - 1.0).abs() < 0.05);
+        assert!((std - 2.0).abs() < 0.05);
+
+        let m = Matrix::random_truncated_normal(100, 100, 1.0, 2.0, &mut rng);
+        assert!(m.iter().all(|x| (x - 1.0).abs() <= 4.0));
+    }
+
+    #[test]
+    fn test_random_bernoulli() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let m = Matrix::random_bernoulli(100, 100, 0.3, &mut rng);
+        assert!(m.iter().all(|&x| x == 0.0 || x == 1.0));
+        let (mean, _) = mean_std(&m);
+        assert!((mean - 0.3).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_shuffle_rows() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let x = Matrix::from_iter(5, 2, (0..10).map(|x| x as f32));
+        let y = Matrix::from_iter(5, 1, (0..5).map(|x| x as f32 * 2.0));
+
+        let mut shuffled = x.clone();
+        let permutation = shuffled.shuffle_rows(&mut rng);
+        let y = y.select_rows(&permutation);
+
+        let mut sorted = permutation.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec![0, 1, 2, 3, 4]);
+        for row in 0..5 {
+            assert_eq!(shuffled.get(row, 0), Some(&y[row]));
+            assert_eq!(
+                shuffled.get_row_matrix(row),
+                x.get_row_matrix(permutation[row])
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use super::Matrix;
+
+/// `1 / (1 + e^-x)`, evaluated as `e^x / (1 + e^x)` for negative `x` so that
+/// `e^-x` can't overflow.
+pub fn sigmoid(x: f32) -> f32 {
+    if x >= 0.0 {
+        1.0 / (1.0 + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (1.0 + e)
+    }
+}
+
+impl Matrix {
+    /// Replaces every row `x` with `e^x / sum(e^x)`. The row maximum is
+    /// subtracted first, so no exponent exceeds 0.
+    pub fn softmax_rows(&mut self) {
+        for row in self.data.chunks_mut(self.cols) {
+            let max = row_max(row);
+            row.iter_mut().for_each(|x| *x = (*x - max).exp());
+            let sum = row.iter().sum::<f32>();
+            row.iter_mut().for_each(|x| *x /= sum);
+        }
+    }
+
+    /// Replaces every row `x` with `x - ln(sum(e^x))`, the logarithm of its
+    /// softmax, without computing the softmax itself so that tiny
+    /// probabilities keep their precision instead of becoming `ln(0)`.
+    pub fn log_softmax_rows(&mut self) {
+        for row in self.data.chunks_mut(self.cols) {
+            let max = row_max(row);
+            let log_sum = row.iter().map(|x| (x - max).exp()).sum::<f32>().ln();
+            row.iter_mut().for_each(|x| *x = (*x - max) - log_sum);
+        }
+    }
+}
+
+/// The largest value of a row, or 0 if it is infinite so that rows of all
+/// `-inf` don't become NaN.
+fn row_max(row: &[f32]) -> f32 {
+    let max = row.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    if max.is_finite() {
+        max
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(m: &Matrix, expected: [[f32; 3]; 3]) {
+        for (row, expected) in expected.iter().enumerate() {
+            for (a, b) in m.get_row(row).unwrap().zip(expected) {
+                assert!((a - b).abs() < 1e-6, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_sigmoid_extremes() {
+        assert_eq!(sigmoid(1000.0), 1.0);
+        assert_eq!(sigmoid(-1000.0), 0.0);
+        assert_eq!(sigmoid(0.0), 0.5);
+        assert!((sigmoid(-80.0) - 1.8048513e-35).abs() < 1e-40);
+        assert!((sigmoid(2.0) + sigmoid(-2.0) - 1.0).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_softmax_extremes() {
+        let logits = Matrix::from_iter(
+            3,
+            3,
+            vec![
+                1000.0, -1000.0, 0.0, //
+                -1000.0, -1000.0, -1000.0, //
+                1.0, 2.0, 3.0,
+            ],
+        );
+
+        let mut softmax = logits.clone();
+        softmax.softmax_rows();
+        assert!(softmax.iter().all(|x| x.is_finite()));
+        let third = 1.0 / 3.0;
+        close(
+            &softmax,
+            [
+                [1.0, 0.0, 0.0],
+                [third, third, third],
+                [0.09003057, 0.24472847, 0.66524096],
+            ],
+        );
+
+        let mut log_softmax = logits.clone();
+        log_softmax.log_softmax_rows();
+        assert!(log_softmax.iter().all(|x| x.is_finite()));
+        let ln3 = 3f32.ln();
+        close(
+            &log_softmax,
+            [
+                [0.0, -2000.0, -1000.0],
+                [-ln3, -ln3, -ln3],
+                [-2.407606, -1.407606, -0.407606],
+            ],
+        );
+    }
+}
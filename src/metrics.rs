@@ -0,0 +1,16 @@
+mod classification;
+mod regression;
+
+pub(crate) use self::classification::labels;
+pub use self::classification::*;
+pub use self::regression::*;
+
+use crate::matrix::Matrix;
+
+/// Predictions and targets are matrices of the same shape with one row per
+/// sample, and at least one row.
+pub(super) fn check(predicted: &Matrix, target: &Matrix) {
+    assert_eq!(predicted.rows(), target.rows());
+    assert_eq!(predicted.cols(), target.cols());
+    assert!(predicted.rows() > 0);
+}
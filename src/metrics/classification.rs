@@ -0,0 +1,364 @@
+use super::check;
+use crate::matrix::Matrix;
+
+/// How per-class scores are combined into one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Average {
+    /// The score of class 1 only, i.e. the positive class of a binary task.
+    Binary,
+    /// The unweighted mean of the per-class scores.
+    Macro,
+    /// The score of the true and false positives and negatives summed over
+    /// all classes.
+    Micro,
+    /// The mean of the per-class scores weighted by their number of targets.
+    Weighted,
+}
+
+/// The receiver operating characteristic of a binary classifier: the false
+/// and true positive rates when predicting positive for scores at or above
+/// each threshold, from the highest threshold down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RocCurve {
+    pub fpr: Vec<f32>,
+    pub tpr: Vec<f32>,
+    pub thresholds: Vec<f32>,
+}
+
+/// Keeps `ln` finite for predictions of exactly 0 or 1.
+const EPS: f32 = 1e-7;
+
+// Predictions are scores (such as probabilities) and targets are one-hot,
+// with a column per class. A single column holds the probability and the 0/1
+// target of the positive class of a binary task.
+
+/// The predicted or target class of every row: the largest column, or for a
+/// single column whether the value is at least 0.5.
+pub(crate) fn labels(m: &Matrix) -> Vec<usize> {
+    match m.cols() {
+        1 => m.iter().map(|&x| (x >= 0.5) as usize).collect(),
+        _ => m.argmax_rows(),
+    }
+}
+
+fn classes(m: &Matrix) -> usize {
+    m.cols().max(2)
+}
+
+/// The fraction of rows whose predicted class is the target class.
+pub fn accuracy(predicted: &Matrix, target: &Matrix) -> f32 {
+    check(predicted, target);
+    let correct = labels(predicted)
+        .into_iter()
+        .zip(labels(target))
+        .filter(|(p, t)| p == t)
+        .count();
+    correct as f32 / target.rows() as f32
+}
+
+/// The fraction of rows whose target class is among the `k` highest
+/// predicted scores.
+pub fn top_k_accuracy(predicted: &Matrix, target: &Matrix, k: usize) -> f32 {
+    check(predicted, target);
+    let scores = class_scores(predicted);
+    let correct = labels(target)
+        .into_iter()
+        .zip(scores.chunks(scores.cols()))
+        .filter(|(t, row)| row.iter().filter(|&&x| x > row[*t]).count() < k)
+        .count();
+    correct as f32 / target.rows() as f32
+}
+
+/// `classes x classes` counts, with the target class as the row and the
+/// predicted class as the column.
+pub fn confusion_matrix(predicted: &Matrix, target: &Matrix) -> Matrix {
+    check(predicted, target);
+    let classes = classes(target);
+    let mut counts = Matrix::new(classes, classes);
+    for (p, t) in labels(predicted).into_iter().zip(labels(target)) {
+        *counts.get_mut(t, p).unwrap() += 1.0;
+    }
+    counts
+}
+
+/// `tp / (tp + fp)`, or 0 for classes that are never predicted.
+pub fn precision(predicted: &Matrix, target: &Matrix, average: Average) -> f32 {
+    let counts = confusion_matrix(predicted, target);
+    averaged(&counts, average, |tp, fp, _| ratio(tp, tp + fp))
+}
+
+/// `tp / (tp + fn)`, or 0 for classes without targets.
+pub fn recall(predicted: &Matrix, target: &Matrix, average: Average) -> f32 {
+    let counts = confusion_matrix(predicted, target);
+    averaged(&counts, average, |tp, _, fn_| ratio(tp, tp + fn_))
+}
+
+/// The harmonic mean of precision and recall, `2 tp / (2 tp + fp + fn)`.
+pub fn f1_score(predicted: &Matrix, target: &Matrix, average: Average) -> f32 {
+    let counts = confusion_matrix(predicted, target);
+    averaged(&counts, average, |tp, fp, fn_| {
+        ratio(2.0 * tp, 2.0 * tp + fp + fn_)
+    })
+}
+
+fn ratio(a: f32, b: f32) -> f32 {
+    if b == 0.0 {
+        0.0
+    } else {
+        a / b
+    }
+}
+
+/// Applies `score(tp, fp, fn)` to the counts of every class and combines
+/// the results.
+fn averaged<F>(counts: &Matrix, average: Average, score: F) -> f32
+where
+    F: Fn(f32, f32, f32) -> f32,
+{
+    let classes = counts.rows();
+    let class = |c: usize| {
+        let tp = *counts.get(c, c).unwrap();
+        let predicted = counts.get_col(c).unwrap().sum::<f32>();
+        let actual = counts.get_row(c).unwrap().sum::<f32>();
+        (tp, predicted - tp, actual - tp, actual)
+    };
+
+    match average {
+        Average::Binary => {
+            let (tp, fp, fn_, _) = class(1);
+            score(tp, fp, fn_)
+        }
+        Average::Macro => {
+            (0..classes)
+                .map(|c| {
+                    let (tp, fp, fn_, _) = class(c);
+                    score(tp, fp, fn_)
+                })
+                .sum::<f32>()
+                / classes as f32
+        }
+        Average::Micro => {
+            let (mut tp, mut fp, mut fn_) = (0.0, 0.0, 0.0);
+            for c in 0..classes {
+                let counts = class(c);
+                tp += counts.0;
+                fp += counts.1;
+                fn_ += counts.2;
+            }
+            score(tp, fp, fn_)
+        }
+        Average::Weighted => {
+            let total = counts.iter().sum::<f32>();
+            (0..classes)
+                .map(|c| {
+                    let (tp, fp, fn_, actual) = class(c);
+                    score(tp, fp, fn_) * actual / total
+                })
+                .sum()
+        }
+    }
+}
+
+/// The mean negative log-likelihood of the targets, `-sum(y ln p)` per row,
+/// or the binary cross-entropy for a single column.
+pub fn log_loss(predicted: &Matrix, target: &Matrix) -> f32 {
+    check(predicted, target);
+    let total = class_scores(predicted)
+        .iter()
+        .zip(class_scores(target).iter())
+        .map(|(p, y)| -y * p.clamp(EPS, 1.0 - EPS).ln())
+        .sum::<f32>();
+    total / target.rows() as f32
+}
+
+/// Scores as `rows x classes`, expanding a single column `p` into
+/// `[1 - p, p]`.
+fn class_scores(m: &Matrix) -> Matrix {
+    match m.cols() {
+        1 => Matrix::from_iter(m.rows(), 2, m.iter().flat_map(|&p| [1.0 - p, p])),
+        _ => m.clone(),
+    }
+}
+
+/// The scores and 0/1 targets of class `c`, as one-vs-rest binary tasks.
+fn one_vs_rest(predicted: &Matrix, target: &Matrix, c: usize) -> (Vec<f32>, Vec<bool>) {
+    let (predicted, target) = (class_scores(predicted), class_scores(target));
+    let scores = predicted.get_col(c).unwrap().copied().collect();
+    let positive = target.get_col(c).unwrap().map(|&y| y == 1.0).collect();
+    (scores, positive)
+}
+
+/// Cumulative `(threshold, tp, fp)` counts at every distinct score, from the
+/// highest down.
+fn cumulative(scores: &[f32], positive: &[bool]) -> Vec<(f32, f32, f32)> {
+    let mut order = (0..scores.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let mut points: Vec<(f32, f32, f32)> = Vec::new();
+    let (mut tp, mut fp) = (0.0, 0.0);
+    for (i, &row) in order.iter().enumerate() {
+        match positive[row] {
+            true => tp += 1.0,
+            false => fp += 1.0,
+        }
+        let last = i + 1 == order.len() || scores[order[i + 1]] != scores[row];
+        if last {
+            points.push((scores[row], tp, fp));
+        }
+    }
+    points
+}
+
+/// The ROC curve of a single column of positive class scores, starting at
+/// `(0, 0)` with an infinite threshold.
+pub fn roc_curve(predicted: &Matrix, target: &Matrix) -> RocCurve {
+    check(predicted, target);
+    assert_eq!(predicted.cols(), 1, "roc_curve expects a binary task");
+    let (scores, positive) = one_vs_rest(predicted, target, 1);
+    roc(&scores, &positive)
+}
+
+fn roc(scores: &[f32], positive: &[bool]) -> RocCurve {
+    let positives = positive.iter().filter(|&&p| p).count() as f32;
+    let negatives = positive.len() as f32 - positives;
+    assert!(
+        positives > 0.0 && negatives > 0.0,
+        "the ROC curve needs positive and negative targets"
+    );
+
+    let mut curve = RocCurve {
+        fpr: vec![0.0],
+        tpr: vec![0.0],
+        thresholds: vec![f32::INFINITY],
+    };
+    for (threshold, tp, fp) in cumulative(scores, positive) {
+        curve.fpr.push(fp / negatives);
+        curve.tpr.push(tp / positives);
+        curve.thresholds.push(threshold);
+    }
+    curve
+}
+
+/// The area under the ROC curve; the one-vs-rest macro average over the
+/// classes for more than one column.
+pub fn roc_auc(predicted: &Matrix, target: &Matrix) -> f32 {
+    check(predicted, target);
+    per_class(predicted, target, |scores, positive| {
+        let curve = roc(scores, positive);
+        let mut area = 0.0;
+        for i in 1..curve.fpr.len() {
+            area += (curve.fpr[i] - curve.fpr[i - 1]) * (curve.tpr[i] + curve.tpr[i - 1]) / 2.0;
+        }
+        area
+    })
+}
+
+/// The area under the precision-recall curve as average precision,
+/// `sum((R_n - R_{n-1}) P_n)` over the thresholds; the macro average over
+/// the classes for more than one column.
+pub fn pr_auc(predicted: &Matrix, target: &Matrix) -> f32 {
+    check(predicted, target);
+    per_class(predicted, target, |scores, positive| {
+        let positives = positive.iter().filter(|&&p| p).count() as f32;
+        assert!(positives > 0.0, "the PR curve needs positive targets");
+        let mut area = 0.0;
+        let mut recall = 0.0;
+        for (_, tp, fp) in cumulative(scores, positive) {
+            area += (tp / positives - recall) * tp / (tp + fp);
+            recall = tp / positives;
+        }
+        area
+    })
+}
+
+/// `metric` of the positive class for a single column, otherwise the mean
+/// over the one-vs-rest tasks of every class.
+fn per_class<F>(predicted: &Matrix, target: &Matrix, metric: F) -> f32
+where
+    F: Fn(&[f32], &[bool]) -> f32,
+{
+    let classes = match predicted.cols() {
+        1 => vec![1],
+        cols => (0..cols).collect(),
+    };
+    let total = classes
+        .iter()
+        .map(|&c| {
+            let (scores, positive) = one_vs_rest(predicted, target, c);
+            metric(&scores, &positive)
+        })
+        .sum::<f32>();
+    total / classes.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::one_hot;
+
+    fn close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_multiclass() {
+        // The example of scikit-learn's `precision_recall_fscore_support`.
+        let target = one_hot(&[0, 1, 2, 0, 1, 2], 3);
+        let predicted = one_hot(&[0, 2, 1, 0, 0, 1], 3);
+
+        close(accuracy(&predicted, &target), 1.0 / 3.0);
+        assert_eq!(
+            confusion_matrix(&predicted, &target),
+            Matrix::from_iter(3, 3, vec![2.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 2.0, 0.0])
+        );
+        close(precision(&predicted, &target, Average::Macro), 0.2222);
+        close(precision(&predicted, &target, Average::Micro), 0.3333);
+        close(precision(&predicted, &target, Average::Weighted), 0.2222);
+        close(recall(&predicted, &target, Average::Macro), 0.3333);
+        close(f1_score(&predicted, &target, Average::Macro), 0.2667);
+        close(f1_score(&predicted, &target, Average::Micro), 0.3333);
+        close(f1_score(&predicted, &target, Average::Weighted), 0.2667);
+    }
+
+    #[test]
+    fn test_binary() {
+        let target = Matrix::from_iter(4, 1, vec![0.0, 0.0, 1.0, 1.0]);
+        let predicted = Matrix::from_iter(4, 1, vec![0.1, 0.4, 0.35, 0.8]);
+
+        close(accuracy(&predicted, &target), 0.75);
+        close(precision(&predicted, &target, Average::Binary), 1.0);
+        close(recall(&predicted, &target, Average::Binary), 0.5);
+        close(roc_auc(&predicted, &target), 0.75);
+        close(pr_auc(&predicted, &target), 0.8333);
+
+        let curve = roc_curve(&predicted, &target);
+        assert_eq!(curve.thresholds, vec![f32::INFINITY, 0.8, 0.4, 0.35, 0.1]);
+        assert_eq!(curve.fpr, vec![0.0, 0.0, 0.5, 0.5, 1.0]);
+        assert_eq!(curve.tpr, vec![0.0, 0.5, 0.5, 1.0, 1.0]);
+
+        // Tied scores form a single point.
+        let tied = Matrix::from_iter(4, 1, vec![0.5, 0.5, 0.5, 0.9]);
+        assert_eq!(roc_curve(&tied, &target).fpr, vec![0.0, 0.0, 1.0]);
+        close(roc_auc(&tied, &target), 0.75);
+    }
+
+    #[test]
+    fn test_log_loss_and_top_k() {
+        // scikit-learn's `log_loss` and `top_k_accuracy_score` examples.
+        let target = one_hot(&[1, 0, 0, 1], 2);
+        let predicted = Matrix::from_iter(4, 2, vec![0.1, 0.9, 0.9, 0.1, 0.8, 0.2, 0.35, 0.65]);
+        close(log_loss(&predicted, &target), 0.21616);
+        let binary = Matrix::from_iter(4, 1, vec![0.9, 0.1, 0.2, 0.65]);
+        close(log_loss(&binary, &target.slice_cols(1, 2)), 0.21616);
+
+        let target = one_hot(&[0, 1, 2, 2], 3);
+        let predicted = Matrix::from_iter(
+            4,
+            3,
+            vec![0.5, 0.2, 0.2, 0.3, 0.4, 0.2, 0.2, 0.4, 0.3, 0.7, 0.2, 0.1],
+        );
+        close(top_k_accuracy(&predicted, &target, 2), 0.75);
+        close(top_k_accuracy(&predicted, &target, 1), 0.5);
+        close(roc_auc(&predicted, &target), 2.0 / 3.0);
+    }
+}
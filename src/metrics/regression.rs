@@ -0,0 +1,261 @@
+use super::check;
+use crate::matrix::Matrix;
+use std::fmt;
+
+// Every metric is reported per output column.
+
+/// `f(predicted, target)` of every output column.
+fn per_column<F>(predicted: &Matrix, target: &Matrix, f: F) -> Vec<f32>
+where
+    F: Fn(&[f32], &[f32]) -> f32,
+{
+    check(predicted, target);
+    (0..target.cols())
+        .map(|c| {
+            let p = predicted.get_col(c).unwrap().copied().collect::<Vec<_>>();
+            let t = target.get_col(c).unwrap().copied().collect::<Vec<_>>();
+            f(&p, &t)
+        })
+        .collect()
+}
+
+fn mean(xs: &[f32]) -> f32 {
+    xs.iter().sum::<f32>() / xs.len() as f32
+}
+
+fn variance(xs: &[f32]) -> f32 {
+    let mean = mean(xs);
+    xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / xs.len() as f32
+}
+
+/// `1 - unexplained / total`, or 1 for a perfect fit of a constant target
+/// and 0 for any other fit of one.
+fn explained(unexplained: f32, total: f32) -> f32 {
+    if total != 0.0 {
+        1.0 - unexplained / total
+    } else if unexplained == 0.0 {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// The `target - predicted` residuals of every row and output.
+pub fn residuals(predicted: &Matrix, target: &Matrix) -> Matrix {
+    check(predicted, target);
+    target - predicted
+}
+
+/// The mean of `|target - predicted|`.
+pub fn mean_absolute_error(predicted: &Matrix, target: &Matrix) -> Vec<f32> {
+    per_column(predicted, target, |p, t| {
+        p.iter().zip(t).map(|(p, t)| (t - p).abs()).sum::<f32>() / t.len() as f32
+    })
+}
+
+/// The square root of the mean of `(target - predicted)^2`.
+pub fn root_mean_squared_error(predicted: &Matrix, target: &Matrix) -> Vec<f32> {
+    per_column(predicted, target, |p, t| {
+        let mse = p.iter().zip(t).map(|(p, t)| (t - p) * (t - p)).sum::<f32>() / t.len() as f32;
+        mse.sqrt()
+    })
+}
+
+/// The mean of `|target - predicted| / |target|` as a fraction, not a
+/// percentage. Targets of 0 are replaced by `f32::EPSILON`, so they give huge
+/// but finite errors.
+pub fn mean_absolute_percentage_error(predicted: &Matrix, target: &Matrix) -> Vec<f32> {
+    per_column(predicted, target, |p, t| {
+        let errors = p
+            .iter()
+            .zip(t)
+            .map(|(p, t)| (t - p).abs() / t.abs().max(f32::EPSILON));
+        errors.sum::<f32>() / t.len() as f32
+    })
+}
+
+/// The coefficient of determination, `1 - SS_res / SS_tot`: 1 for a perfect
+/// fit, 0 for always predicting the mean and negative for worse.
+pub fn r2_score(predicted: &Matrix, target: &Matrix) -> Vec<f32> {
+    per_column(predicted, target, |p, t| {
+        let ss_res = p.iter().zip(t).map(|(p, t)| (t - p) * (t - p)).sum::<f32>();
+        explained(ss_res, variance(t) * t.len() as f32)
+    })
+}
+
+/// `1 - Var(target - predicted) / Var(target)`. Unlike [`r2_score`] it
+/// ignores a constant bias in the predictions.
+pub fn explained_variance_score(predicted: &Matrix, target: &Matrix) -> Vec<f32> {
+    per_column(predicted, target, |p, t| {
+        let residuals = p.iter().zip(t).map(|(p, t)| t - p).collect::<Vec<_>>();
+        explained(variance(&residuals), variance(t))
+    })
+}
+
+/// The distribution of the `target - predicted` residuals of one output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResidualSummary {
+    pub mean: f32,
+    pub std: f32,
+    pub min: f32,
+    pub median: f32,
+    pub max: f32,
+}
+
+impl ResidualSummary {
+    /// The summary of every output column.
+    pub fn new(predicted: &Matrix, target: &Matrix) -> Vec<Self> {
+        let residuals = residuals(predicted, target);
+        (0..residuals.cols())
+            .map(|c| {
+                let mut r = residuals.get_col(c).unwrap().copied().collect::<Vec<_>>();
+                r.sort_by(f32::total_cmp);
+                let n = r.len();
+                let median = match n % 2 {
+                    1 => r[n / 2],
+                    _ => (r[n / 2 - 1] + r[n / 2]) / 2.0,
+                };
+                ResidualSummary {
+                    mean: mean(&r),
+                    std: variance(&r).sqrt(),
+                    min: r[0],
+                    median,
+                    max: r[n - 1],
+                }
+            })
+            .collect()
+    }
+}
+
+/// Every regression metric of every output column, displayed as one line per
+/// output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegressionReport {
+    pub mae: Vec<f32>,
+    pub rmse: Vec<f32>,
+    pub mape: Vec<f32>,
+    pub r2: Vec<f32>,
+    pub explained_variance: Vec<f32>,
+    pub residuals: Vec<ResidualSummary>,
+}
+
+impl RegressionReport {
+    pub fn new(predicted: &Matrix, target: &Matrix) -> Self {
+        RegressionReport {
+            mae: mean_absolute_error(predicted, target),
+            rmse: root_mean_squared_error(predicted, target),
+            mape: mean_absolute_percentage_error(predicted, target),
+            r2: r2_score(predicted, target),
+            explained_variance: explained_variance_score(predicted, target),
+            residuals: ResidualSummary::new(predicted, target),
+        }
+    }
+}
+
+impl fmt::Display for RegressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, r) in self.residuals.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "output_{i}: mae: {:.6} rmse: {:.6} mape: {:.6} r2: {:.6} \
+                 explained_variance: {:.6} residuals: mean {:.6} std {:.6} \
+                 min {:.6} median {:.6} max {:.6}",
+                self.mae[i],
+                self.rmse[i],
+                self.mape[i],
+                self.r2[i],
+                self.explained_variance[i],
+                r.mean,
+                r.std,
+                r.min,
+                r.median,
+                r.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1e-4, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        // The first column is scikit-learn's example.
+        let target = Matrix::from_iter(
+            4,
+            3,
+            vec![
+                3.0, 0.5, 1.0, -0.5, -1.0, 1.0, 2.0, 7.0, -6.0, 7.0, 0.0, 0.0,
+            ],
+        );
+        let predicted = Matrix::from_iter(
+            4,
+            3,
+            vec![2.5, 0.0, 2.0, 0.0, -1.0, 2.0, 2.0, 8.0, -5.0, 8.0, 0.0, 0.0],
+        );
+
+        close(
+            &mean_absolute_error(&predicted, &target),
+            &[0.5, 0.375, 0.75],
+        );
+        close(
+            &root_mean_squared_error(&predicted, &target),
+            &[0.375f32.sqrt(), 0.3125f32.sqrt(), 0.75f32.sqrt()],
+        );
+        close(
+            &r2_score(&predicted, &target),
+            &[0.948608, 0.968504, 0.911765],
+        );
+        close(
+            &explained_variance_score(&predicted, &target),
+            &[0.957173, 0.970079, 0.977941],
+        );
+        close(
+            &mean_absolute_percentage_error(&predicted, &target),
+            &[0.327381, 0.285714, 0.541667],
+        );
+        let zero = Matrix::from_iter(1, 1, vec![0.0]);
+        let mape = mean_absolute_percentage_error(&zero.map(|x| x + 1.0), &zero);
+        assert!(mape[0] > 1e6 && mape[0].is_finite());
+
+        let constant = Matrix::from_iter(2, 1, vec![1.0, 1.0]);
+        close(&r2_score(&constant, &constant), &[1.0]);
+        close(&r2_score(&constant.map(|x| x * 2.0), &constant), &[0.0]);
+    }
+
+    #[test]
+    fn test_residuals() {
+        let target = Matrix::from_iter(4, 1, vec![3.0, -0.5, 2.0, 7.0]);
+        let predicted = Matrix::from_iter(4, 1, vec![2.5, 0.0, 2.0, 8.0]);
+        assert_eq!(
+            residuals(&predicted, &target),
+            Matrix::from_iter(4, 1, vec![0.5, -0.5, 0.0, -1.0])
+        );
+        let summary = &ResidualSummary::new(&predicted, &target)[0];
+        close(
+            &[
+                summary.mean,
+                summary.std,
+                summary.min,
+                summary.median,
+                summary.max,
+            ],
+            &[-0.25, 0.5590, -1.0, -0.25, 0.5],
+        );
+
+        let report = RegressionReport::new(&predicted, &target);
+        assert!(report.to_string().starts_with("output_0: mae: 0.500000 "));
+    }
+}
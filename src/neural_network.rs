@@ -1,11 +1,24 @@
+use super::activation::Activation;
+use super::init::Init;
+use super::layer::{Dropout, Layer, Mode, Norm};
+use super::loss::Loss;
 use super::matrix::Matrix;
+use super::regularizer::Regularizer;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 #[derive(Clone, Debug)]
 pub struct NeuralNetwork {
     size: usize,
+    mode: Mode,
     weight: Vec<Matrix>,
     bias: Vec<Matrix>,
     activation: Vec<Matrix>,
+    activation_fn: Vec<Activation>,
+    dropout: Vec<Option<Dropout>>,
+    norm: Vec<Option<Norm>>,
+    regularizer: Vec<Option<Regularizer>>,
+    loss: Loss,
 }
 
 impl NeuralNetwork {
@@ -25,9 +38,15 @@ impl NeuralNetwork {
 
         let mut nn = NeuralNetwork {
             size: size - 1,
+            mode: Mode::Train,
             weight: Vec::with_capacity(size),
             bias: Vec::with_capacity(size),
             activation: Vec::with_capacity(size),
+            activation_fn: vec![Activation::Sigmoid; size - 1],
+            dropout: vec![None; size - 1],
+            norm: vec![None; size - 1],
+            regularizer: vec![None; size - 1],
+            loss: Loss::Mse,
         };
 
         nn.activation.push(Matrix::from_iter(
@@ -47,6 +66,184 @@ impl NeuralNetwork {
         nn
     }
 
+    /// Creates a network whose weights and biases are drawn from `weight` and
+    /// `bias`, seeded with `seed`.
+    pub fn with_init(data: &[usize], weight: Init, bias: Init, seed: u64) -> Self {
+        let mut nn = Self::new(data);
+        nn.init(weight, bias, seed);
+        nn
+    }
+
+    /// Re-initializes every layer, using its input and output sizes as the
+    /// fan-in and fan-out.
+    pub fn init(&mut self, weight: Init, bias: Init, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for i in 0..self.size {
+            let (fan_in, fan_out) = (self.weight[i].rows(), self.weight[i].cols());
+            self.weight[i] = weight.sample(fan_in, fan_out, fan_in, fan_out, &mut rng);
+            self.bias[i] = bias.sample(1, fan_out, fan_in, fan_out, &mut rng);
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Switches to training mode, enabling dropout.
+    pub fn train(&mut self) {
+        self.set_mode(Mode::Train);
+    }
+
+    /// Switches to inference mode, disabling dropout.
+    pub fn eval(&mut self) {
+        self.set_mode(Mode::Eval);
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        for dropout in self.dropout.iter_mut().flatten() {
+            dropout.set_mode(mode);
+        }
+        for norm in self.norm.iter_mut().flatten() {
+            norm.set_mode(mode);
+        }
+    }
+
+    /// Drops out the inputs of layer `layer` (`0` being the network input)
+    /// with probability `rate` while in training mode.
+    pub fn set_dropout(&mut self, layer: usize, rate: f32, seed: u64) {
+        assert!(layer < self.size);
+        let mut dropout = Dropout::new(rate, seed);
+        dropout.set_mode(self.mode);
+        self.dropout[layer] = Some(dropout);
+    }
+
+    /// Reseeds the dropout of every layer, layer `i` with `seed + i`.
+    pub fn reseed(&mut self, seed: u64) {
+        for (i, dropout) in self.dropout.iter_mut().enumerate() {
+            if let Some(dropout) = dropout {
+                dropout.reseed(seed.wrapping_add(i as u64));
+            }
+        }
+    }
+
+    /// Normalizes the pre-activation of layer `layer`, i.e. `norm` is applied
+    /// between the bias and the activation function.
+    pub fn set_norm(&mut self, layer: usize, mut norm: Norm) {
+        assert!(layer < self.size);
+        assert_eq!(norm.features(), self.weight[layer].cols());
+        norm.set_mode(self.mode);
+        self.norm[layer] = Some(norm);
+    }
+
+    /// The normalization of every layer, with the batch norm running
+    /// statistics, to be put back by `restore_norms`.
+    pub(crate) fn norms(&self) -> Vec<Option<Norm>> {
+        self.norm.clone()
+    }
+
+    pub(crate) fn restore_norms(&mut self, norm: Vec<Option<Norm>>) {
+        self.norm = norm;
+    }
+
+    /// Penalizes the weights (not the bias) of layer `layer` in `cost` and
+    /// `backprop`.
+    pub fn set_regularizer(&mut self, layer: usize, regularizer: Regularizer) {
+        assert!(layer < self.size);
+        self.regularizer[layer] = Some(regularizer);
+    }
+
+    pub fn regularizers(&self) -> &[Option<Regularizer>] {
+        &self.regularizer
+    }
+
+    /// The sum of the weight penalties of every regularized layer.
+    pub fn penalty(&self) -> f32 {
+        self.regularizer
+            .iter()
+            .zip(&self.weight)
+            .filter_map(|(r, weight)| r.map(|r| r.penalty(weight)))
+            .sum()
+    }
+
+    /// Sets the activation function of layer `layer`, sigmoid by default.
+    pub fn set_activation(&mut self, layer: usize, activation: Activation) {
+        assert!(layer < self.size);
+        self.activation_fn[layer] = activation;
+    }
+
+    pub fn activations(&self) -> &[Activation] {
+        &self.activation_fn
+    }
+
+    /// Sets the loss used by `cost` and `backprop`, squared error by default.
+    pub fn set_loss(&mut self, loss: Loss) {
+        self.loss = loss;
+    }
+
+    pub fn loss(&self) -> Loss {
+        self.loss
+    }
+
+    /// The number of neurons per layer, input first, as passed to `new`.
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![self.activation[0].cols()];
+        sizes.extend(self.weight.iter().map(|w| w.cols()));
+        sizes
+    }
+
+    /// Every learned parameter under a stable name: `layers.{i}.weight`,
+    /// `layers.{i}.bias`, plus `layers.{i}.norm.weight` and
+    /// `layers.{i}.norm.bias` for normalized layers.
+    pub fn parameters(&self) -> Vec<(String, &Matrix)> {
+        self.named(false)
+    }
+
+    /// Same as `parameters`, in the same order.
+    pub fn parameters_mut(&mut self) -> Vec<(String, &mut Matrix)> {
+        self.named_mut(false)
+    }
+
+    /// The parameters plus the running statistics of batch norms, i.e.
+    /// everything needed to restore the network.
+    pub fn tensors(&self) -> Vec<(String, &Matrix)> {
+        self.named(true)
+    }
+
+    /// Same as `tensors`, in the same order.
+    pub fn tensors_mut(&mut self) -> Vec<(String, &mut Matrix)> {
+        self.named_mut(true)
+    }
+
+    fn named(&self, buffers: bool) -> Vec<(String, &Matrix)> {
+        let mut tensors = Vec::new();
+        for i in 0..self.size {
+            tensors.push((format!("layers.{i}.weight"), &self.weight[i]));
+            tensors.push((format!("layers.{i}.bias"), &self.bias[i]));
+            for (name, tensor) in self.norm[i].iter().flat_map(|n| n.named(buffers)) {
+                tensors.push((format!("layers.{i}.norm.{name}"), tensor));
+            }
+        }
+        tensors
+    }
+
+    fn named_mut(&mut self, buffers: bool) -> Vec<(String, &mut Matrix)> {
+        let mut tensors = Vec::new();
+        let layers = self
+            .weight
+            .iter_mut()
+            .zip(&mut self.bias)
+            .zip(&mut self.norm);
+        for (i, ((weight, bias), norm)) in layers.enumerate() {
+            tensors.push((format!("layers.{i}.weight"), weight));
+            tensors.push((format!("layers.{i}.bias"), bias));
+            for (name, tensor) in norm.iter_mut().flat_map(|n| n.named_mut(buffers)) {
+                tensors.push((format!("layers.{i}.norm.{name}"), tensor));
+            }
+        }
+        tensors
+    }
+
     pub fn set_input_take(&mut self, input: Matrix) {
         assert_eq!(self.activation[0].rows(), input.rows());
         assert_eq!(self.activation[0].cols(), input.cols());
@@ -68,43 +265,58 @@ impl NeuralNetwork {
         &self.activation[self.size]
     }
 
+    /// The output activation of every layer from the last forward pass.
+    pub(crate) fn outputs(&self) -> &[Matrix] {
+        &self.activation[1..]
+    }
+
+    /// Runs the network on every row of the input activation. In training
+    /// mode, dropout is applied to the inputs of each layer; the stored
+    /// activations keep their values from before dropout, which `backprop`
+    /// needs for the derivatives of the activation functions.
     pub fn forward(&mut self) -> &Matrix {
         for i in 0..self.size {
             let (prev_layer, next_layer) = self.activation.split_at_mut(i + 1);
             let next_activation = &mut next_layer[0];
-            let activation = &prev_layer[i];
+            let dropped;
+            let activation = match &mut self.dropout[i] {
+                Some(dropout) => {
+                    dropped = dropout.forward(&prev_layer[i]);
+                    &dropped
+                }
+                None => &prev_layer[i],
+            };
             let weight = &self.weight[i];
             let bias = &self.bias[i];
 
+            if next_activation.rows() != activation.rows() {
+                *next_activation = Matrix::new(activation.rows(), weight.cols());
+            }
             next_activation.dot_from(activation, weight);
-            next_activation.add_from(bias);
-            next_activation.sigmoid();
+            next_activation.add_row_from(bias);
+            if let Some(norm) = &mut self.norm[i] {
+                *next_activation = norm.forward(next_activation);
+            }
+            self.activation_fn[i].apply(next_activation);
         }
 
         &self.activation.last().unwrap()
     }
 
+    /// The cost of `input` in the current mode, including the weight
+    /// penalties. The dropout generators and batch norm running statistics
+    /// are left as they were, so repeated calls, as in `finite_diff`, see the
+    /// same dropout masks as the next `backprop`.
     pub fn cost(&mut self, input: &Matrix, output: &Matrix) -> f32 {
         assert!(input.rows() == output.rows());
         assert!(output.cols() == self.activation[self.size].cols());
 
-        let mut result = 0.0;
-        let n = input.rows();
-
-        for training in 0..input.rows() {
-            let x = input.get_row_matrix(training).unwrap();
-            let truth_ouput = output.get_row_matrix(training).unwrap();
-
-            self.set_input_take(x);
-            let model_output = self.forward();
-
-            for col in 0..output.cols() {
-                let d = model_output.get(0, col).unwrap() - truth_ouput.get(0, col).unwrap();
-                result += d * d;
-            }
-        }
-
-        result / (n as f32)
+        let (dropout, norm) = (self.dropout.clone(), self.norm.clone());
+        self.activation[0] = input.clone();
+        self.forward();
+        let cost = self.loss.cost(&self.activation[self.size], output) + self.penalty();
+        (self.dropout, self.norm) = (dropout, norm);
+        cost
     }
 
     pub fn finite_diff(&mut self, gradient: &mut Self, eps: &f32, input: &Matrix, output: &Matrix) {
@@ -132,6 +344,48 @@ impl NeuralNetwork {
         }
     }
 
+    /// Computes the gradient of `cost`, including the weight penalties, by
+    /// backpropagation, running the whole batch through a single `forward`
+    /// pass.
+    pub fn backprop(&mut self, gradient: &mut Self, input: &Matrix, output: &Matrix) {
+        assert!(input.rows() == output.rows());
+        assert!(output.cols() == self.activation[self.size].cols());
+
+        self.activation[0] = input.clone();
+        self.forward();
+
+        // d(cost)/d(output), then through the activation function.
+        let mut delta = self.loss.gradient(&self.activation[self.size], output);
+
+        for i in (0..self.size).rev() {
+            delta.mul_from(&self.activation_fn[i].derivative(&self.activation[i + 1]));
+            if let Some(norm) = &mut self.norm[i] {
+                let norm_gradient = gradient.norm[i].get_or_insert_with(|| norm.clone());
+                delta = norm.backward(norm_gradient, &delta);
+            }
+
+            gradient.weight[i] = match &self.dropout[i] {
+                Some(dropout) => {
+                    let mut input = self.activation[i].clone();
+                    input.mul_from(dropout.mask());
+                    &input.transpose() * &delta
+                }
+                None => &self.activation[i].transpose() * &delta,
+            };
+            gradient.bias[i] = delta.sum_rows();
+            if let Some(regularizer) = &self.regularizer[i] {
+                regularizer.add_gradient(&self.weight[i], &mut gradient.weight[i]);
+            }
+
+            if i > 0 {
+                delta = &delta * &self.weight[i].transpose();
+                if let Some(dropout) = &self.dropout[i] {
+                    delta.mul_from(dropout.mask());
+                }
+            }
+        }
+    }
+
     pub fn learn(&mut self, gradient: &mut Self, rate: &f32) {
         for i in 0..self.size {
             for row in 0..self.weight[i].rows() {
@@ -147,14 +401,93 @@ impl NeuralNetwork {
                         rate * gradient.bias[i].get(row, col).unwrap();
                 }
             }
+
+            if let (Some(norm), Some(norm_gradient)) = (&mut self.norm[i], &mut gradient.norm[i]) {
+                norm.learn(norm_gradient, rate);
+            }
         }
     }
 
+    /// Runs `input` through the network in eval mode, i.e. without dropout.
     pub fn test(&mut self, input: &Matrix) -> Matrix {
+        let mode = self.mode;
+        self.set_mode(Mode::Eval);
         self.activation[0] = input.clone();
         self.forward();
+        self.set_mode(mode);
         self.activation[self.size].clone()
     }
+
+    /// Monte Carlo dropout: runs `input` through the network `samples` times
+    /// with dropout active and returns the mean and variance of the outputs.
+    pub fn mc_dropout(&mut self, input: &Matrix, samples: usize) -> (Matrix, Matrix) {
+        assert!(samples > 0);
+        let mode = self.mode;
+        self.set_mode(Mode::Train);
+
+        let cols = self.activation[self.size].cols();
+        let mut mean = Matrix::new(input.rows(), cols);
+        let mut square = Matrix::new(input.rows(), cols);
+        for _ in 0..samples {
+            self.activation[0] = input.clone();
+            let output = self.forward();
+            mean.add_from(output);
+            square.add_from(&output.map(|x| x * x));
+        }
+        self.set_mode(mode);
+
+        let n = samples as f32;
+        mean.scale(1.0 / n);
+        square.scale(1.0 / n);
+        let mut variance = &square - &mean.map(|x| x * x);
+        variance.apply(|x| x.max(0.0));
+        (mean, variance)
+    }
+
+    /// Runs `input` through the network in eval mode like `test`, but keeps
+    /// the intermediate activations in `workspace` instead of `self`, so a
+    /// shared network can serve several threads, each with its own workspace.
+    pub fn predict<'a>(&self, input: &Matrix, workspace: &'a mut Workspace) -> &'a Matrix {
+        assert_eq!(input.cols(), self.weight[0].rows());
+        let activation = &mut workspace.activation;
+        activation.resize_with(self.size + 1, || Matrix::new(0, 0));
+        if activation[0].rows() != input.rows() || activation[0].cols() != input.cols() {
+            activation[0] = input.clone();
+        } else {
+            activation[0].copy_from(input);
+        }
+
+        for i in 0..self.size {
+            let (prev_layer, next_layer) = activation.split_at_mut(i + 1);
+            let (input, output) = (&prev_layer[i], &mut next_layer[0]);
+            let weight = &self.weight[i];
+
+            if output.rows() != input.rows() || output.cols() != weight.cols() {
+                *output = Matrix::new(input.rows(), weight.cols());
+            }
+            output.dot_from(input, weight);
+            output.add_row_from(&self.bias[i]);
+            if let Some(norm) = &self.norm[i] {
+                *output = norm.infer(output);
+            }
+            self.activation_fn[i].apply(output);
+        }
+
+        &activation[self.size]
+    }
+}
+
+/// Scratch activations for [`NeuralNetwork::predict`]. A workspace can be
+/// reused across calls and networks; it is resized as needed.
+#[derive(Clone, Debug, Default)]
+pub struct Workspace {
+    activation: Vec<Matrix>,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
 }
 
 impl std::fmt::Display for NeuralNetwork {
@@ -177,6 +510,20 @@ impl std::fmt::Display for NeuralNetwork {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layer::{assert_close, numeric_grad, BatchNorm1d, LayerNorm};
+
+    #[test]
+    fn test_with_init() {
+        let nn = NeuralNetwork::with_init(&[4, 3, 2], Init::XavierUniform, Init::Zeros, 5);
+        assert_eq!(nn.weight[0].rows(), 4);
+        assert_eq!(nn.weight[1].cols(), 2);
+        assert!(nn.bias.iter().all(|b| b.iter().all(|&x| x == 0.0)));
+        assert_ne!(nn.weight[0][0], nn.weight[0][1]);
+        assert_ne!(nn.weight[0][0], nn.weight[1][0]);
+
+        let other = NeuralNetwork::with_init(&[4, 3, 2], Init::XavierUniform, Init::Zeros, 5);
+        assert_eq!(nn.weight, other.weight);
+    }
 
     #[test]
     fn test_new() {
@@ -357,4 +704,216 @@ mod tests {
 
         assert_eq!(output, Matrix::from_iter(1, 1, vec![0.8761885526812198]));
     }
+
+    #[test]
+    fn test_backprop() {
+        let mut nn = NeuralNetwork::new(&[2, 3, 1]);
+        nn.weight[0] = Matrix::from_iter(2, 3, vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+        nn.weight[1] = Matrix::from_iter(3, 1, vec![0.7, 0.8, 0.9]);
+        nn.bias[0] = Matrix::from_iter(1, 3, vec![0.1, 0.2, 0.3]);
+        nn.bias[1] = Matrix::from_iter(1, 1, vec![0.4]);
+
+        let input = Matrix::from_iter(2, 2, vec![0.1, 0.2, 0.3, 0.4]);
+        let output = Matrix::from_iter(2, 1, vec![0.5, 0.6]);
+
+        let mut expected = NeuralNetwork::new(&[2, 3, 1]);
+        nn.finite_diff(&mut expected, &1e-3, &input, &output);
+        let mut gradient = NeuralNetwork::new(&[2, 3, 1]);
+        nn.backprop(&mut gradient, &input, &output);
+
+        for i in 0..2 {
+            for (a, b) in gradient.weight[i].iter().zip(expected.weight[i].iter()) {
+                assert!((a - b).abs() < 1e-3, "{a} != {b}");
+            }
+            for (a, b) in gradient.bias[i].iter().zip(expected.bias[i].iter()) {
+                assert!((a - b).abs() < 1e-3, "{a} != {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_backprop_dropout() {
+        let weights = (0..).map(|i| ((i as f32) * 0.7 + 0.2).sin());
+        let mut nn = NeuralNetwork::from_iter(&[3, 4, 4, 1], weights);
+        nn.set_dropout(0, 0.25, 1);
+        nn.set_dropout(1, 0.5, 2);
+        nn.set_dropout(2, 0.5, 3);
+
+        let input = Matrix::from_iter(4, 3, (0..12).map(|i| ((i as f32) * 0.4).cos()));
+        let output = Matrix::from_iter(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
+
+        // `cost` restores the generators, so every evaluation of
+        // `finite_diff` and the `backprop` after it share the same masks.
+        assert_eq!(nn.cost(&input, &output), nn.cost(&input, &output));
+        let mut expected = NeuralNetwork::new(&[3, 4, 4, 1]);
+        nn.finite_diff(&mut expected, &1e-3, &input, &output);
+        let mut gradient = NeuralNetwork::new(&[3, 4, 4, 1]);
+        nn.backprop(&mut gradient, &input, &output);
+        assert!(nn.dropout[1].as_ref().unwrap().mask().contains(&0.0));
+
+        for i in 0..3 {
+            for (a, b) in gradient.weight[i].iter().zip(expected.weight[i].iter()) {
+                assert!((a - b).abs() < 2e-3, "{a} != {b}");
+            }
+            for (a, b) in gradient.bias[i].iter().zip(expected.bias[i].iter()) {
+                assert!((a - b).abs() < 2e-3, "{a} != {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_dropout_modes() {
+        let mut nn = NeuralNetwork::from_iter(&[4, 8, 1], std::iter::repeat(0.5));
+        nn.set_dropout(1, 0.5, 3);
+        let input = Matrix::from_iter(1, 4, vec![1.0, 0.5, -0.5, 0.2]);
+
+        let expected = nn.test(&input);
+        assert_eq!(nn.mode(), Mode::Train);
+
+        nn.eval();
+        nn.activation[0] = input.clone();
+        assert_eq!(nn.forward(), &expected);
+
+        nn.train();
+        nn.activation[0] = input.clone();
+        nn.forward();
+        assert_eq!(nn.dropout[1].as_ref().unwrap().mask().len(), 8);
+        assert!(nn.dropout[1].as_ref().unwrap().mask().contains(&0.0));
+
+        let (mean, variance) = nn.mc_dropout(&input, 200);
+        assert_eq!(nn.mode(), Mode::Train);
+        assert!((mean[0] - expected[0]).abs() < 0.05);
+        assert!(variance[0] > 0.0);
+    }
+
+    #[test]
+    fn test_backprop_norm() {
+        let mut weights = (0..).map(|i| ((i as f32) * 0.7).sin());
+        let mut nn = NeuralNetwork::new(&[2, 3, 3, 1]);
+        for i in 0..3 {
+            let (rows, cols) = (nn.weight[i].rows(), nn.weight[i].cols());
+            nn.weight[i] = Matrix::from_iter(rows, cols, weights.by_ref());
+        }
+        nn.set_norm(0, Norm::Batch(BatchNorm1d::new(3)));
+        nn.set_norm(1, Norm::Layer(LayerNorm::new(3)));
+
+        let input = Matrix::from_iter(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let output = Matrix::from_iter(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
+
+        let mut expected = NeuralNetwork::new(&[2, 3, 3, 1]);
+        nn.finite_diff(&mut expected, &1e-3, &input, &output);
+        let mut gradient = NeuralNetwork::new(&[2, 3, 3, 1]);
+        nn.backprop(&mut gradient, &input, &output);
+
+        for i in 0..3 {
+            for (a, b) in gradient.weight[i].iter().zip(expected.weight[i].iter()) {
+                assert!((a - b).abs() < 2e-3, "{a} != {b}");
+            }
+        }
+
+        let before = nn.cost(&input, &output);
+        for _ in 0..200 {
+            nn.backprop(&mut gradient, &input, &output);
+            nn.learn(&mut gradient, &0.5);
+        }
+        assert!(nn.cost(&input, &output) < before);
+
+        nn.eval();
+        let output = nn.test(&input);
+        assert_eq!(output.rows(), 4);
+    }
+
+    #[test]
+    fn test_backprop_activations() {
+        let weights = (0..).map(|i| ((i as f32) * 0.9).cos());
+        let mut nn = NeuralNetwork::from_iter(&[2, 3, 3, 1], weights);
+        nn.set_activation(0, Activation::Relu);
+        nn.set_activation(1, Activation::Tanh);
+        nn.set_activation(2, Activation::Identity);
+
+        let input = Matrix::from_iter(3, 2, vec![0.5, -1.0, 1.0, 0.25, -0.5, 0.75]);
+        let output = Matrix::from_iter(3, 1, vec![1.0, -1.0, 0.5]);
+
+        let mut expected = NeuralNetwork::new(&[2, 3, 3, 1]);
+        nn.finite_diff(&mut expected, &1e-3, &input, &output);
+        let mut gradient = NeuralNetwork::new(&[2, 3, 3, 1]);
+        nn.backprop(&mut gradient, &input, &output);
+
+        for i in 0..3 {
+            for (a, b) in gradient.weight[i].iter().zip(expected.weight[i].iter()) {
+                assert!((a - b).abs() < 1e-2, "{a} != {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_backprop_regularized() {
+        let weights = (0..).map(|i| ((i as f32) * 0.8 + 0.3).sin());
+        let mut nn = NeuralNetwork::from_iter(&[2, 3, 3, 1], weights);
+        nn.set_regularizer(0, Regularizer::l1(0.05));
+        nn.set_regularizer(1, Regularizer::l2(0.1));
+        nn.set_regularizer(2, Regularizer::elastic_net(0.2, 0.5));
+
+        let input = Matrix::from_iter(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let output = Matrix::from_iter(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
+
+        let mut expected = NeuralNetwork::new(&[2, 3, 3, 1]);
+        nn.finite_diff(&mut expected, &1e-3, &input, &output);
+        let mut gradient = NeuralNetwork::new(&[2, 3, 3, 1]);
+        nn.backprop(&mut gradient, &input, &output);
+
+        let mut plain = nn.clone();
+        plain.regularizer = vec![None; 3];
+        let mut unregularized = NeuralNetwork::new(&[2, 3, 3, 1]);
+        plain.backprop(&mut unregularized, &input, &output);
+        assert!(
+            (nn.cost(&input, &output) - plain.cost(&input, &output) - nn.penalty()).abs() < 1e-6
+        );
+
+        for i in 0..3 {
+            for (a, b) in gradient.weight[i].iter().zip(expected.weight[i].iter()) {
+                assert!((a - b).abs() < 2e-3, "{a} != {b}");
+            }
+            // The regularizer's share of the gradient is that of `penalty`.
+            let penalty = numeric_grad(&nn.weight[i], |w| {
+                let mut nn = nn.clone();
+                nn.weight[i] = w.clone();
+                nn.penalty()
+            });
+            assert_close(
+                &(&gradient.weight[i] - &unregularized.weight[i]),
+                &penalty,
+                1e-2,
+            );
+            assert_eq!(gradient.bias[i], unregularized.bias[i]);
+        }
+    }
+
+    #[test]
+    fn test_predict_shared() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<NeuralNetwork>();
+
+        let mut nn = NeuralNetwork::with_init(&[2, 4, 3, 2], Init::HeNormal, Init::HeUniform, 9);
+        nn.set_dropout(1, 0.5, 1);
+        nn.set_norm(0, Norm::Batch(BatchNorm1d::new(4)));
+        nn.set_norm(1, Norm::Layer(LayerNorm::new(3)));
+        nn.set_activation(0, Activation::Relu);
+        let input = Matrix::from_iter(5, 2, (0..10).map(|x| (x as f32 * 0.3).cos()));
+        nn.backprop(&mut nn.clone(), &input, &Matrix::new(5, 2));
+        let expected = nn.test(&input);
+
+        std::thread::scope(|scope| {
+            for rows in [1, 5] {
+                let (nn, input, expected) = (&nn, &input, &expected);
+                scope.spawn(move || {
+                    let mut workspace = Workspace::new();
+                    for _ in 0..2 {
+                        let output = nn.predict(&input.slice_rows(0, rows), &mut workspace);
+                        assert_eq!(output, &expected.slice_rows(0, rows));
+                    }
+                });
+            }
+        });
+    }
 }
@@ -0,0 +1,511 @@
+mod proto;
+
+use self::proto::{fields, floats, ints, Message, Value};
+use crate::activation::Activation;
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+use std::fmt;
+use std::path::Path;
+
+const IR_VERSION: i64 = 8;
+const OPSET_VERSION: i64 = 13;
+const FLOAT: i64 = 1;
+
+#[derive(Debug)]
+pub enum OnnxError {
+    Io(std::io::Error),
+    /// The bytes are not a well-formed protobuf message.
+    Decode(String),
+    /// The model uses an operator or feature outside the dense subset.
+    Unsupported(String),
+    /// The graph is well-formed but does not describe a dense network.
+    Invalid(String),
+}
+
+impl fmt::Display for OnnxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnnxError::Io(err) => write!(f, "{err}"),
+            OnnxError::Decode(msg) => write!(f, "invalid ONNX protobuf: {msg}"),
+            OnnxError::Unsupported(msg) => write!(f, "unsupported ONNX model: {msg}"),
+            OnnxError::Invalid(msg) => write!(f, "invalid ONNX graph: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for OnnxError {}
+
+impl From<std::io::Error> for OnnxError {
+    fn from(err: std::io::Error) -> Self {
+        OnnxError::Io(err)
+    }
+}
+
+impl From<String> for OnnxError {
+    fn from(msg: String) -> Self {
+        OnnxError::Decode(msg)
+    }
+}
+
+impl NeuralNetwork {
+    /// Exports the network as an ONNX model (opset 13) with one `Gemm` per
+    /// layer followed by its activation, a float input named `input` of
+    /// shape `[batch, inputs]` and an output named `output`.
+    ///
+    /// Dropout is left out as it is an identity at inference time; layers
+    /// with normalization cannot be exported.
+    pub fn to_onnx(&self) -> Result<Vec<u8>, OnnxError> {
+        let sizes = self.sizes();
+        let layers = sizes.len() - 1;
+        let tensors = self.tensors();
+        if tensors.len() != 2 * layers {
+            return Err(OnnxError::Unsupported(
+                "normalization layers cannot be exported".to_string(),
+            ));
+        }
+
+        let mut graph = Message::default();
+        let mut current = "input".to_string();
+        for (i, activation) in self.activations().iter().enumerate() {
+            let output = match i + 1 == layers {
+                true => "output".to_string(),
+                false => format!("layers.{i}.output"),
+            };
+            let gemm = match activation {
+                Activation::Identity => output.clone(),
+                _ => format!("layers.{i}.gemm"),
+            };
+            let (weight, bias) = (&tensors[2 * i], &tensors[2 * i + 1]);
+            graph.message(
+                1,
+                &node(
+                    &format!("layers.{i}.gemm"),
+                    "Gemm",
+                    &[&current, &weight.0, &bias.0],
+                    &gemm,
+                ),
+            );
+            let op = match activation {
+                Activation::Sigmoid => Some("Sigmoid"),
+                Activation::Relu => Some("Relu"),
+                Activation::Tanh => Some("Tanh"),
+                Activation::Identity => None,
+            };
+            if let Some(op) = op {
+                let name = format!("layers.{i}.{}", activation);
+                graph.message(1, &node(&name, op, &[&gemm], &output));
+            }
+            current = output;
+        }
+
+        graph.string(2, "rustml");
+        for (name, tensor) in &tensors {
+            let dims = match name.ends_with(".weight") {
+                true => vec![tensor.rows(), tensor.cols()],
+                false => vec![tensor.len()],
+            };
+            graph.message(5, &initializer(name, &dims, tensor));
+        }
+        graph.message(11, &value_info("input", sizes[0]));
+        graph.message(12, &value_info("output", sizes[layers]));
+
+        let mut opset = Message::default();
+        opset.string(1, "").int(2, OPSET_VERSION);
+        let mut model = Message::default();
+        model
+            .int(1, IR_VERSION)
+            .string(2, "rustml")
+            .string(3, env!("CARGO_PKG_VERSION"))
+            .message(7, &graph)
+            .message(8, &opset);
+        Ok(model.0)
+    }
+
+    pub fn write_onnx<P: AsRef<Path>>(&self, path: P) -> Result<(), OnnxError> {
+        Ok(std::fs::write(path, self.to_onnx()?)?)
+    }
+
+    /// Imports a chain of dense layers: `Gemm` (`transB` is allowed) or
+    /// `MatMul` followed by an optional `Add` of the bias, each followed by
+    /// an optional `Sigmoid`, `Relu`, `Tanh` or `Identity`. Weights must be
+    /// float initializers.
+    pub fn from_onnx(bytes: &[u8]) -> Result<Self, OnnxError> {
+        let model = fields(bytes)?;
+        let graph = model
+            .iter()
+            .find(|(field, _)| *field == 7)
+            .and_then(|(_, value)| value.as_bytes())
+            .ok_or_else(|| OnnxError::Invalid("model has no graph".to_string()))?;
+        let graph = fields(graph)?;
+
+        let mut initializers = Vec::new();
+        for (_, value) in graph.iter().filter(|(field, _)| *field == 5) {
+            initializers.push(read_tensor(value)?);
+        }
+        let lookup = |name: &str| {
+            initializers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, tensor)| tensor.clone())
+                .ok_or_else(|| OnnxError::Invalid(format!("`{name}` is not an initializer")))
+        };
+
+        let mut current = graph
+            .iter()
+            .filter(|(field, _)| *field == 11)
+            .map(|(_, value)| Ok(fields(value.as_bytes().unwrap_or_default())?))
+            .collect::<Result<Vec<_>, OnnxError>>()?
+            .iter()
+            .filter_map(|input| string(input, 1))
+            .find(|name| lookup(name).is_err())
+            .ok_or_else(|| OnnxError::Invalid("graph has no input".to_string()))?;
+
+        // Each layer is (weight, bias, activation); the last one stays open
+        // until an activation closes it.
+        let mut layers: Vec<(Matrix, Matrix, Option<Activation>)> = Vec::new();
+        for (_, value) in graph.iter().filter(|(field, _)| *field == 1) {
+            let node = fields(value.as_bytes().unwrap_or_default())?;
+            let op = string(&node, 4).unwrap_or_default();
+            let inputs = strings(&node, 1);
+            let output = string(&node, 2)
+                .ok_or_else(|| OnnxError::Invalid(format!("`{op}` node has no output")))?;
+
+            let consumed = inputs
+                .iter()
+                .position(|input| *input == current)
+                .ok_or_else(|| {
+                    OnnxError::Invalid(format!("`{op}` node does not consume `{current}`"))
+                })?;
+            let other = inputs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != consumed)
+                .map(|(_, name)| name.as_str())
+                .collect::<Vec<_>>();
+
+            let open = layers.last_mut().filter(|layer| layer.2.is_none());
+            match (op.as_str(), open) {
+                ("Gemm", _) => {
+                    for (name, expected) in [("transA", 0.0), ("alpha", 1.0), ("beta", 1.0)] {
+                        if attribute(&node, name)?.is_some_and(|v| v != expected) {
+                            return Err(OnnxError::Unsupported(format!("Gemm with {name}")));
+                        }
+                    }
+                    let mut weight = lookup(other.first().copied().unwrap_or_default())?;
+                    if attribute(&node, "transB")? == Some(1.0) {
+                        weight = weight.transpose();
+                    }
+                    let bias = match other.get(1) {
+                        Some(name) => lookup(name)?,
+                        None => Matrix::new(1, weight.cols()),
+                    };
+                    close(&mut layers);
+                    layers.push((weight, bias, None));
+                }
+                ("MatMul", _) => {
+                    let weight = lookup(other.first().copied().unwrap_or_default())?;
+                    let bias = Matrix::new(1, weight.cols());
+                    close(&mut layers);
+                    layers.push((weight, bias, None));
+                }
+                ("Add", Some((_, bias, _))) => {
+                    let add = lookup(other.first().copied().unwrap_or_default())?;
+                    if add.len() != bias.len() {
+                        return Err(OnnxError::Invalid(format!(
+                            "`{output}` adds {} values to {} outputs",
+                            add.len(),
+                            bias.len()
+                        )));
+                    }
+                    bias.add_from(&Matrix::from_iter(1, bias.cols(), add.iter().copied()));
+                }
+                ("Sigmoid" | "Relu" | "Tanh" | "Identity", Some((_, _, activation))) => {
+                    *activation = Some(op.to_lowercase().parse().unwrap());
+                }
+                ("Add" | "Sigmoid" | "Relu" | "Tanh" | "Identity", None) => {
+                    return Err(OnnxError::Unsupported(format!(
+                        "`{op}` without a preceding dense layer"
+                    )))
+                }
+                _ => return Err(OnnxError::Unsupported(format!("operator `{op}`"))),
+            }
+            current = output;
+        }
+        close(&mut layers);
+
+        if layers.is_empty() {
+            return Err(OnnxError::Invalid("graph has no layers".to_string()));
+        }
+        let mut sizes = vec![layers[0].0.rows()];
+        for (i, (weight, bias, _)) in layers.iter().enumerate() {
+            if weight.rows() != sizes[i] || bias.len() != weight.cols() {
+                return Err(OnnxError::Invalid(format!(
+                    "layer {i} has mismatched shapes"
+                )));
+            }
+            sizes.push(weight.cols());
+        }
+
+        let mut nn = NeuralNetwork::new(&sizes);
+        for (i, (_, _, activation)) in layers.iter().enumerate() {
+            nn.set_activation(i, activation.unwrap());
+        }
+        let values = layers.into_iter().flat_map(|(weight, bias, _)| {
+            let bias = Matrix::from_iter(1, bias.len(), bias.iter().copied());
+            [weight, bias]
+        });
+        for ((_, tensor), value) in nn.tensors_mut().into_iter().zip(values) {
+            *tensor = value;
+        }
+        Ok(nn)
+    }
+
+    pub fn read_onnx<P: AsRef<Path>>(path: P) -> Result<Self, OnnxError> {
+        Self::from_onnx(&std::fs::read(path)?)
+    }
+}
+
+/// Marks a layer without an activation node as linear.
+fn close(layers: &mut [(Matrix, Matrix, Option<Activation>)]) {
+    if let Some((_, _, activation @ None)) = layers.last_mut() {
+        *activation = Some(Activation::Identity);
+    }
+}
+
+fn node(name: &str, op: &str, inputs: &[&str], output: &str) -> Message {
+    let mut node = Message::default();
+    for input in inputs {
+        node.string(1, input);
+    }
+    node.string(2, output).string(3, name).string(4, op);
+    node
+}
+
+fn initializer(name: &str, dims: &[usize], tensor: &Matrix) -> Message {
+    let mut message = Message::default();
+    for &dim in dims {
+        message.int(1, dim as i64);
+    }
+    let raw = tensor
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<_>>();
+    message.int(2, FLOAT).string(8, name).bytes(9, &raw);
+    message
+}
+
+/// A float tensor of shape `[batch, features]`.
+fn value_info(name: &str, features: usize) -> Message {
+    let mut batch = Message::default();
+    batch.string(2, "batch");
+    let mut features_dim = Message::default();
+    features_dim.int(1, features as i64);
+    let mut shape = Message::default();
+    shape.message(1, &batch).message(1, &features_dim);
+
+    let mut tensor = Message::default();
+    tensor.int(1, FLOAT).message(2, &shape);
+    let mut ty = Message::default();
+    ty.message(1, &tensor);
+    let mut info = Message::default();
+    info.string(1, name).message(2, &ty);
+    info
+}
+
+/// Reads a `TensorProto` with one or two dimensions into a matrix.
+fn read_tensor(value: &Value) -> Result<(String, Matrix), OnnxError> {
+    let tensor = fields(value.as_bytes().unwrap_or_default())?;
+    let name = string(&tensor, 8).unwrap_or_default();
+    let data_type = tensor
+        .iter()
+        .find(|(field, _)| *field == 2)
+        .and_then(|(_, value)| value.as_int());
+    if data_type != Some(FLOAT) {
+        return Err(OnnxError::Unsupported(format!(
+            "initializer `{name}` is not float"
+        )));
+    }
+
+    let dims = ints(&tensor, 1)?;
+    let dim = |d: i64| usize::try_from(d).ok();
+    let shape = match dims[..] {
+        [] => Some((1, 1)),
+        [n] => dim(n).map(|n| (1, n)),
+        [r, c] => dim(r).zip(dim(c)),
+        _ => {
+            return Err(OnnxError::Unsupported(format!(
+                "initializer `{name}` has {} dimensions",
+                dims.len()
+            )))
+        }
+    };
+    let count = shape.and_then(|(rows, cols)| rows.checked_mul(cols));
+    let (Some((rows, cols)), Some(count)) = (shape, count) else {
+        return Err(OnnxError::Invalid(format!(
+            "initializer `{name}` has invalid shape {dims:?}"
+        )));
+    };
+
+    let raw = tensor
+        .iter()
+        .find(|(field, _)| *field == 9)
+        .and_then(|(_, value)| value.as_bytes());
+    let data = match raw {
+        Some(raw) if raw.len() % 4 != 0 => {
+            return Err(OnnxError::Invalid(format!(
+                "initializer `{name}` has {} bytes of raw data",
+                raw.len()
+            )))
+        }
+        Some(raw) => raw
+            .chunks(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect(),
+        None => floats(&tensor, 4)?,
+    };
+    if data.len() != count {
+        return Err(OnnxError::Invalid(format!(
+            "initializer `{name}` has {} values for shape {dims:?}",
+            data.len()
+        )));
+    }
+    Ok((name, Matrix::from_iter(rows, cols, data)))
+}
+
+/// Reads an `AttributeProto` of `node` as a number, either `f` or `i`.
+fn attribute(node: &[(u32, Value)], name: &str) -> Result<Option<f32>, OnnxError> {
+    for (_, value) in node.iter().filter(|(field, _)| *field == 5) {
+        let attribute = fields(value.as_bytes().unwrap_or_default())?;
+        if string(&attribute, 1).as_deref() != Some(name) {
+            continue;
+        }
+        for (field, value) in &attribute {
+            match field {
+                2 => return Ok(value.as_float()),
+                3 => return Ok(value.as_int().map(|i| i as f32)),
+                _ => {}
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn string(message: &[(u32, Value)], field: u32) -> Option<String> {
+    message
+        .iter()
+        .find(|(f, _)| *f == field)
+        .and_then(|(_, value)| value.as_string())
+}
+
+fn strings(message: &[(u32, Value)], field: u32) -> Vec<String> {
+    message
+        .iter()
+        .filter(|(f, _)| *f == field)
+        .filter_map(|(_, value)| value.as_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::Init;
+    use crate::layer::{assert_close, LayerNorm, Norm};
+
+    #[test]
+    fn test_round_trip() {
+        let mut nn =
+            NeuralNetwork::with_init(&[3, 5, 4, 2], Init::HeNormal, Init::XavierUniform, 7);
+        nn.set_activation(0, Activation::Relu);
+        nn.set_activation(1, Activation::Tanh);
+
+        let mut loaded = NeuralNetwork::from_onnx(&nn.to_onnx().unwrap()).unwrap();
+        assert_eq!(loaded.sizes(), nn.sizes());
+        assert_eq!(loaded.activations(), nn.activations());
+
+        let input = Matrix::from_iter(4, 3, (0..12).map(|x| (x as f32 * 0.37).sin()));
+        assert_close(&loaded.test(&input), &nn.test(&input), 1e-6);
+    }
+
+    #[test]
+    fn test_import_matmul_add() {
+        // x @ W^T via Gemm(transB=1), then MatMul + Add without an activation.
+        let w0 = Matrix::from_iter(2, 3, vec![1.0, 0.0, -1.0, 0.5, 2.0, 0.0]);
+        let w1 = Matrix::from_iter(2, 1, vec![1.0, -1.0]);
+        let b1 = Matrix::from_iter(1, 1, vec![0.25]);
+
+        let mut trans_b = Message::default();
+        trans_b.string(1, "transB").int(3, 1).int(20, 2);
+        let mut gemm = node("gemm", "Gemm", &["x", "w0"], "h");
+        gemm.message(5, &trans_b);
+
+        let mut graph = Message::default();
+        graph
+            .message(1, &gemm)
+            .message(1, &node("relu", "Relu", &["h"], "a"))
+            .message(1, &node("matmul", "MatMul", &["a", "w1"], "m"))
+            .message(1, &node("add", "Add", &["b1", "m"], "y"))
+            .message(5, &initializer("w0", &[2, 3], &w0))
+            .message(5, &initializer("w1", &[2, 1], &w1))
+            .message(5, &initializer("b1", &[1], &b1))
+            .message(11, &value_info("x", 3));
+        let mut model = Message::default();
+        model.int(1, IR_VERSION).message(7, &graph);
+
+        let mut nn = NeuralNetwork::from_onnx(&model.0).unwrap();
+        assert_eq!(nn.sizes(), vec![3, 2, 1]);
+        assert_eq!(nn.activations(), &[Activation::Relu, Activation::Identity]);
+
+        // h = [1 - 1, 0.5 + 2] = [0, 2.5], y = 0 - 2.5 + 0.25
+        let output = nn.test(&Matrix::from_iter(1, 3, vec![1.0, 1.0, 1.0]));
+        assert_eq!(output, Matrix::from_iter(1, 1, vec![-2.25]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut nn = NeuralNetwork::new(&[2, 2]);
+        nn.set_norm(0, Norm::Layer(LayerNorm::new(2)));
+        assert!(matches!(nn.to_onnx(), Err(OnnxError::Unsupported(_))));
+
+        let mut graph = Message::default();
+        graph
+            .message(1, &node("conv", "Conv", &["x", "w"], "y"))
+            .message(5, &initializer("w", &[1], &Matrix::new(1, 1)))
+            .message(11, &value_info("x", 1));
+        let mut model = Message::default();
+        model.message(7, &graph);
+        let err = NeuralNetwork::from_onnx(&model.0).unwrap_err();
+        assert_eq!(err.to_string(), "unsupported ONNX model: operator `Conv`");
+
+        assert!(matches!(
+            NeuralNetwork::from_onnx(&[0x0A, 0x05]),
+            Err(OnnxError::Decode(_))
+        ));
+
+        let import = |w: &Message| {
+            let mut graph = Message::default();
+            graph
+                .message(1, &node("matmul", "MatMul", &["x", "w"], "y"))
+                .message(5, w)
+                .message(11, &value_info("x", 1));
+            let mut model = Message::default();
+            model.message(7, &graph);
+            NeuralNetwork::from_onnx(&model.0).unwrap_err().to_string()
+        };
+        let mut truncated = Message::default();
+        truncated.int(1, 1).int(1, 3).int(2, FLOAT).string(8, "w");
+        truncated.bytes(9, &[0; 9]);
+        assert_eq!(
+            import(&truncated),
+            "invalid ONNX graph: initializer `w` has 9 bytes of raw data"
+        );
+        let mut huge = Message::default();
+        huge.int(1, i64::MAX).int(1, 4).int(2, FLOAT).string(8, "w");
+        assert_eq!(
+            import(&huge),
+            format!(
+                "invalid ONNX graph: initializer `w` has invalid shape [{}, 4]",
+                i64::MAX
+            )
+        );
+    }
+}
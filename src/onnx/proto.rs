@@ -0,0 +1,196 @@
+//! Minimal protobuf wire format: enough to write and walk the ONNX messages.
+
+/// An encoded message under construction.
+#[derive(Default)]
+pub(super) struct Message(pub(super) Vec<u8>);
+
+impl Message {
+    fn key(&mut self, field: u32, wire_type: u8) {
+        varint(&mut self.0, ((field as u64) << 3) | wire_type as u64);
+    }
+
+    pub(super) fn int(&mut self, field: u32, value: i64) -> &mut Self {
+        self.key(field, 0);
+        varint(&mut self.0, value as u64);
+        self
+    }
+
+    pub(super) fn bytes(&mut self, field: u32, value: &[u8]) -> &mut Self {
+        self.key(field, 2);
+        varint(&mut self.0, value.len() as u64);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    pub(super) fn string(&mut self, field: u32, value: &str) -> &mut Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    pub(super) fn message(&mut self, field: u32, value: &Message) -> &mut Self {
+        self.bytes(field, &value.0)
+    }
+}
+
+fn varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Value<'a> {
+    Varint(u64),
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32([u8; 4]),
+}
+
+impl<'a> Value<'a> {
+    pub(super) fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Varint(v) => Some(*v as i64),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_float(&self) -> Option<f32> {
+        match self {
+            Value::Fixed32(b) => Some(f32::from_le_bytes(*b)),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_string(&self) -> Option<String> {
+        self.as_bytes()
+            .map(|b| String::from_utf8_lossy(b).into_owned())
+    }
+}
+
+/// Splits a message into its `(field, value)` pairs, in wire order.
+pub(super) fn fields(mut bytes: &[u8]) -> Result<Vec<(u32, Value<'_>)>, String> {
+    let mut fields = Vec::new();
+    while !bytes.is_empty() {
+        let key = read_varint(&mut bytes)?;
+        let field = (key >> 3) as u32;
+        let value = match key & 7 {
+            0 => Value::Varint(read_varint(&mut bytes)?),
+            1 => {
+                split(&mut bytes, 8)?;
+                Value::Fixed64
+            }
+            2 => {
+                let len = read_varint(&mut bytes)? as usize;
+                Value::Bytes(split(&mut bytes, len)?)
+            }
+            5 => Value::Fixed32(split(&mut bytes, 4)?.try_into().unwrap()),
+            wire_type => return Err(format!("unsupported wire type {wire_type}")),
+        };
+        fields.push((field, value));
+    }
+    Ok(fields)
+}
+
+/// Decodes a repeated `int64` field, packed or not.
+pub(super) fn ints(fields: &[(u32, Value)], field: u32) -> Result<Vec<i64>, String> {
+    let mut values = Vec::new();
+    for (_, value) in fields.iter().filter(|(f, _)| *f == field) {
+        match value {
+            Value::Varint(v) => values.push(*v as i64),
+            Value::Bytes(mut packed) => {
+                while !packed.is_empty() {
+                    values.push(read_varint(&mut packed)? as i64);
+                }
+            }
+            _ => return Err(format!("field {field} is not an integer")),
+        }
+    }
+    Ok(values)
+}
+
+/// Decodes a repeated `float` field, packed or not.
+pub(super) fn floats(fields: &[(u32, Value)], field: u32) -> Result<Vec<f32>, String> {
+    let mut values = Vec::new();
+    for (_, value) in fields.iter().filter(|(f, _)| *f == field) {
+        match value {
+            Value::Fixed32(b) => values.push(f32::from_le_bytes(*b)),
+            Value::Bytes(packed) if packed.len() % 4 == 0 => values.extend(
+                packed
+                    .chunks(4)
+                    .map(|b| f32::from_le_bytes(b.try_into().unwrap())),
+            ),
+            _ => return Err(format!("field {field} is not a float")),
+        }
+    }
+    Ok(values)
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *split(bytes, 1)?.first().unwrap();
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+    Err("varint is too long".to_string())
+}
+
+fn split<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+    if bytes.len() < len {
+        return Err("truncated message".to_string());
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_round_trip() {
+        let mut inner = Message::default();
+        inner.string(1, "x");
+        let mut message = Message::default();
+        message.int(1, 300).int(2, -1);
+        message.0.push(3 << 3 | 5);
+        message.0.extend_from_slice(&1.5f32.to_le_bytes());
+        message.message(4, &inner);
+        assert_eq!(&message.0[..3], &[0x08, 0xAC, 0x02]);
+
+        let fields = fields(&message.0).unwrap();
+        assert_eq!(fields[0], (1, Value::Varint(300)));
+        assert_eq!(fields[1].1.as_int(), Some(-1));
+        assert_eq!(fields[2].1.as_float(), Some(1.5));
+        let inner = super::fields(fields[3].1.as_bytes().unwrap()).unwrap();
+        assert_eq!(inner[0].1.as_string(), Some("x".to_string()));
+    }
+
+    #[test]
+    fn test_packed() {
+        let mut message = Message::default();
+        message.bytes(1, &[1, 0xAC, 0x02]).int(1, 7);
+        let packed = [1.0f32, 2.0]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        message.bytes(2, &packed);
+        message.0.push(2 << 3 | 5);
+        message.0.extend_from_slice(&3.0f32.to_le_bytes());
+
+        let fields = fields(&message.0).unwrap();
+        assert_eq!(ints(&fields, 1).unwrap(), vec![1, 300, 7]);
+        assert_eq!(floats(&fields, 2).unwrap(), vec![1.0, 2.0, 3.0]);
+    }
+}
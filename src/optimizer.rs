@@ -0,0 +1,210 @@
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+
+/// How an [`Optimizer`] turns gradients into parameter updates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    /// `p -= rate * g`
+    Sgd,
+    /// Heavy ball: `v = momentum * v + g`, then `p -= rate * v`.
+    Momentum { momentum: f32 },
+    /// Adam with bias corrected first and second moment estimates.
+    Adam { beta1: f32, beta2: f32, eps: f32 },
+}
+
+/// Updates the parameters of a [`NeuralNetwork`] from the gradient computed
+/// by `backprop`, keeping one moment buffer per parameter where the method
+/// needs it.
+#[derive(Clone, Debug)]
+pub struct Optimizer {
+    method: Method,
+    rate: f32,
+    updates: u64,
+    first: Vec<Matrix>,
+    second: Vec<Matrix>,
+}
+
+impl Optimizer {
+    pub fn new(method: Method, rate: f32) -> Self {
+        Self {
+            method,
+            rate,
+            updates: 0,
+            first: Vec::new(),
+            second: Vec::new(),
+        }
+    }
+
+    pub fn sgd(rate: f32) -> Self {
+        Self::new(Method::Sgd, rate)
+    }
+
+    pub fn momentum(rate: f32, momentum: f32) -> Self {
+        Self::new(Method::Momentum { momentum }, rate)
+    }
+
+    /// Adam with the usual `beta1 = 0.9`, `beta2 = 0.999` and `eps = 1e-8`.
+    pub fn adam(rate: f32) -> Self {
+        Self::new(
+            Method::Adam {
+                beta1: 0.9,
+                beta2: 0.999,
+                eps: 1e-8,
+            },
+            rate,
+        )
+    }
+
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate;
+    }
+
+    /// The number of updates applied so far.
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    /// The first (momentum, Adam) and second (Adam) moment buffers, in the
+    /// order of [`NeuralNetwork::parameters`]. Empty until the first update.
+    pub fn moments(&self) -> (&[Matrix], &[Matrix]) {
+        (&self.first, &self.second)
+    }
+
+    pub(crate) fn set_state(&mut self, updates: u64, first: Vec<Matrix>, second: Vec<Matrix>) {
+        self.updates = updates;
+        self.first = first;
+        self.second = second;
+    }
+
+    /// Applies one update to the parameters of `nn` using the matching
+    /// parameters of `gradient`.
+    pub fn step(&mut self, nn: &mut NeuralNetwork, gradient: &NeuralNetwork) {
+        let gradients = gradient.parameters();
+        let parameters = nn.parameters_mut();
+        assert_eq!(parameters.len(), gradients.len());
+
+        let zeros = || {
+            gradients
+                .iter()
+                .map(|(_, g)| Matrix::new(g.rows(), g.cols()))
+                .collect::<Vec<_>>()
+        };
+        if self.first.is_empty() && self.method != Method::Sgd {
+            self.first = zeros();
+        }
+        if self.second.is_empty() && matches!(self.method, Method::Adam { .. }) {
+            self.second = zeros();
+        }
+        self.updates += 1;
+
+        for (i, ((_, p), (_, g))) in parameters.into_iter().zip(&gradients).enumerate() {
+            match self.method {
+                Method::Sgd => p.add_scaled_from(g, -self.rate),
+                Method::Momentum { momentum } => {
+                    let v = &mut self.first[i];
+                    v.scale(momentum);
+                    v.add_from(g);
+                    p.add_scaled_from(v, -self.rate);
+                }
+                Method::Adam { beta1, beta2, eps } => {
+                    let (m, v) = (&mut self.first[i], &mut self.second[i]);
+                    m.scale(beta1);
+                    m.add_scaled_from(g, 1.0 - beta1);
+                    v.scale(beta2);
+                    v.add_scaled_from(&g.map(|x| x * x), 1.0 - beta2);
+
+                    let t = self.updates as f32;
+                    let (c1, c2) = (1.0 - beta1.powf(t), 1.0 - beta2.powf(t));
+                    for (k, x) in p.as_mut_slice().iter_mut().enumerate() {
+                        *x -= self.rate * (m[k] / c1) / ((v[k] / c2).sqrt() + eps);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::Init;
+
+    fn xor() -> (Matrix, Matrix) {
+        let input = Matrix::from_iter(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let output = Matrix::from_iter(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
+        (input, output)
+    }
+
+    #[test]
+    fn test_sgd_matches_learn() {
+        let (input, output) = xor();
+        let mut nn = NeuralNetwork::with_init(&[2, 3, 1], Init::XavierUniform, Init::Zeros, 2);
+        let mut expected = nn.clone();
+        let mut gradient = nn.clone();
+
+        nn.backprop(&mut gradient, &input, &output);
+        expected.learn(&mut gradient, &0.5);
+        Optimizer::sgd(0.5).step(&mut nn, &gradient);
+        assert_eq!(nn.parameters(), expected.parameters());
+    }
+
+    #[test]
+    fn test_adam_first_step() {
+        // The first bias corrected Adam step moves every parameter by about
+        // `rate` against the sign of its gradient.
+        let (input, output) = xor();
+        let mut nn = NeuralNetwork::with_init(&[2, 3, 1], Init::XavierUniform, Init::HeUniform, 2);
+        let before = nn.clone();
+        let mut gradient = nn.clone();
+        nn.backprop(&mut gradient, &input, &output);
+
+        let mut optimizer = Optimizer::adam(0.01);
+        optimizer.step(&mut nn, &gradient);
+        assert_eq!(optimizer.updates(), 1);
+        assert_eq!(optimizer.moments().1.len(), 4);
+
+        let params = nn.parameters().into_iter().zip(before.parameters());
+        for (((_, after), (_, before)), (_, g)) in params.zip(gradient.parameters()) {
+            for k in 0..g.len() {
+                let expected = before[k] - 0.01 * g[k].signum();
+                assert!(
+                    (after[k] - expected).abs() < 1e-4,
+                    "{} != {expected}",
+                    after[k]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimizers_reduce_cost() {
+        let (input, output) = xor();
+        for mut optimizer in [
+            Optimizer::sgd(1.0),
+            Optimizer::momentum(0.5, 0.9),
+            Optimizer::adam(0.05),
+        ] {
+            let mut nn = NeuralNetwork::with_init(&[2, 4, 1], Init::XavierUniform, Init::Zeros, 3);
+            let mut gradient = nn.clone();
+            let before = nn.cost(&input, &output);
+            for _ in 0..2000 {
+                nn.backprop(&mut gradient, &input, &output);
+                optimizer.step(&mut nn, &gradient);
+            }
+            let after = nn.cost(&input, &output);
+            assert!(
+                after < before / 2.0,
+                "{:?}: {before} -> {after}",
+                optimizer.method()
+            );
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::matrix::Matrix;
+
+/// A penalty on the weights of a layer, `l1 * sum(|w|) + l2 * sum(w^2)`,
+/// added to the cost so that training prefers small (L2) or sparse (L1)
+/// weights. Biases are never penalized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Regularizer {
+    pub l1: f32,
+    pub l2: f32,
+}
+
+impl Regularizer {
+    pub fn l1(l1: f32) -> Self {
+        Self::new(l1, 0.0)
+    }
+
+    /// Also known as weight decay: for SGD, the penalty shrinks every weight
+    /// by `2 * rate * l2` of itself per step.
+    pub fn l2(l2: f32) -> Self {
+        Self::new(0.0, l2)
+    }
+
+    /// Elastic net, splitting `strength` between the L1 (`l1_ratio`) and
+    /// L2 (`1 - l1_ratio`) penalties.
+    pub fn elastic_net(strength: f32, l1_ratio: f32) -> Self {
+        assert!((0.0..=1.0).contains(&l1_ratio));
+        Self::new(strength * l1_ratio, strength * (1.0 - l1_ratio))
+    }
+
+    fn new(l1: f32, l2: f32) -> Self {
+        assert!(l1 >= 0.0 && l2 >= 0.0);
+        Self { l1, l2 }
+    }
+
+    pub fn penalty(&self, weight: &Matrix) -> f32 {
+        weight
+            .iter()
+            .map(|w| self.l1 * w.abs() + self.l2 * w * w)
+            .sum()
+    }
+
+    /// Adds the derivative of `penalty` to `gradient`, taking the
+    /// derivative of `|w|` at 0 to be 0.
+    pub fn add_gradient(&self, weight: &Matrix, gradient: &mut Matrix) {
+        assert_eq!(weight.rows(), gradient.rows());
+        assert_eq!(weight.cols(), gradient.cols());
+        for (g, w) in gradient.as_mut_slice().iter_mut().zip(weight.iter()) {
+            let sign = if *w == 0.0 { 0.0 } else { w.signum() };
+            *g += self.l1 * sign + 2.0 * self.l2 * w;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::{assert_close, numeric_grad};
+
+    #[test]
+    fn test_gradient() {
+        let weight = Matrix::from_iter(2, 3, vec![0.5, -0.3, 0.8, -1.2, 0.1, 0.4]);
+        let regularizers = [
+            Regularizer::l1(0.1),
+            Regularizer::l2(0.2),
+            Regularizer::elastic_net(0.3, 0.25),
+        ];
+        for regularizer in regularizers {
+            let expected = numeric_grad(&weight, |w| regularizer.penalty(w));
+            let mut gradient = Matrix::new(2, 3);
+            regularizer.add_gradient(&weight, &mut gradient);
+            assert_close(&gradient, &expected, 1e-2);
+        }
+
+        let elastic = Regularizer::elastic_net(0.4, 0.25);
+        assert!((elastic.l1 - 0.1).abs() < 1e-7 && (elastic.l2 - 0.3).abs() < 1e-7);
+        assert!((Regularizer::l1(1.0).penalty(&weight) - 3.3).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,470 @@
+use crate::activation::Activation;
+use crate::json::{Json, JsonError};
+use crate::layer::{BatchNorm1d, LayerNorm, Norm};
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+use std::borrow::Borrow;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum SafetensorsError {
+    Io(std::io::Error),
+    Json(JsonError),
+    /// The header is valid JSON but not a valid safetensors header.
+    Header(String),
+    /// Only `F32` and `F64` tensors can be loaded.
+    DataType {
+        name: String,
+        dtype: String,
+    },
+    Shape {
+        name: String,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+    Missing(String),
+    Unexpected(String),
+    Truncated {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for SafetensorsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SafetensorsError::Io(err) => write!(f, "{err}"),
+            SafetensorsError::Json(err) => write!(f, "invalid safetensors header: {err}"),
+            SafetensorsError::Header(msg) => write!(f, "invalid safetensors header: {msg}"),
+            SafetensorsError::DataType { name, dtype } => {
+                write!(f, "tensor `{name}` has unsupported dtype {dtype}")
+            }
+            SafetensorsError::Shape {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "tensor `{name}` has shape {found:?}, expected {expected:?}"
+            ),
+            SafetensorsError::Missing(name) => write!(f, "missing tensor `{name}`"),
+            SafetensorsError::Unexpected(name) => write!(f, "unexpected tensor `{name}`"),
+            SafetensorsError::Truncated { expected, found } => {
+                write!(f, "expected {expected} bytes, found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SafetensorsError {}
+
+impl From<std::io::Error> for SafetensorsError {
+    fn from(err: std::io::Error) -> Self {
+        SafetensorsError::Io(err)
+    }
+}
+
+impl From<JsonError> for SafetensorsError {
+    fn from(err: JsonError) -> Self {
+        SafetensorsError::Json(err)
+    }
+}
+
+/// The tensors and string metadata of a safetensors file.
+pub(crate) type Safetensors = (Vec<(String, Vec<usize>, Matrix)>, Vec<(String, String)>);
+
+/// Serializes `tensors` as little-endian `F32`: an 8 byte header length, the
+/// JSON header padded with spaces to a multiple of 8 bytes, then the data.
+pub(crate) fn serialize<M: Borrow<Matrix>>(
+    tensors: &[(String, Vec<usize>, M)],
+    metadata: &[(String, String)],
+) -> Vec<u8> {
+    let mut header = Vec::new();
+    if !metadata.is_empty() {
+        let metadata = metadata
+            .iter()
+            .map(|(key, value)| (key.clone(), Json::from(value.as_str())))
+            .collect();
+        header.push(("__metadata__".to_string(), Json::Object(metadata)));
+    }
+    let mut offset = 0;
+    for (name, shape, tensor) in tensors {
+        let tensor = tensor.borrow();
+        assert_eq!(shape.iter().product::<usize>(), tensor.len());
+        let end = offset + 4 * tensor.len();
+        let info = Json::Object(vec![
+            ("dtype".to_string(), Json::from("F32")),
+            ("shape".to_string(), Json::from(shape.clone())),
+            ("data_offsets".to_string(), Json::from(vec![offset, end])),
+        ]);
+        header.push((name.clone(), info));
+        offset = end;
+    }
+
+    let mut header = Json::Object(header).to_string();
+    header.push_str(&" ".repeat((8 - header.len() % 8) % 8));
+
+    let mut bytes = Vec::with_capacity(8 + header.len() + offset);
+    bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for (_, _, tensor) in tensors {
+        for x in tensor.borrow().iter() {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Parses a safetensors file. `F64` data is narrowed to `f32`; a tensor of
+/// shape `[n]` becomes `1 x n` and higher ranks are flattened into the
+/// columns after the first dimension.
+pub(crate) fn deserialize(bytes: &[u8]) -> Result<Safetensors, SafetensorsError> {
+    let len = take(bytes, 0, 8)?;
+    let len = u64::from_le_bytes(len.try_into().unwrap()) as usize;
+    let header = std::str::from_utf8(take(bytes, 8, len)?)
+        .map_err(|_| SafetensorsError::Header("header is not UTF-8".to_string()))?;
+    let data = &bytes[8..][len..];
+
+    let invalid = |name: &str, field: &str| {
+        SafetensorsError::Header(format!("invalid `{field}` for tensor `{name}`"))
+    };
+    let header = Json::parse(header)?;
+    let entries = header
+        .as_object()
+        .ok_or_else(|| SafetensorsError::Header("header is not an object".to_string()))?;
+
+    let (mut tensors, mut metadata) = (Vec::new(), Vec::new());
+    for (name, info) in entries {
+        if name == "__metadata__" {
+            for (key, value) in info.as_object().ok_or_else(|| invalid(name, name))? {
+                let value = value.as_str().ok_or_else(|| invalid(name, key))?;
+                metadata.push((key.clone(), value.to_string()));
+            }
+            continue;
+        }
+
+        let dtype = info
+            .get("dtype")
+            .and_then(Json::as_str)
+            .ok_or_else(|| invalid(name, "dtype"))?;
+        let shape = info
+            .get("shape")
+            .and_then(Json::as_array)
+            .and_then(|s| s.iter().map(Json::as_usize).collect::<Option<Vec<_>>>())
+            .ok_or_else(|| invalid(name, "shape"))?;
+        let offsets = info
+            .get("data_offsets")
+            .and_then(Json::as_array)
+            .and_then(|s| s.iter().map(Json::as_usize).collect::<Option<Vec<_>>>())
+            .filter(|o| o.len() == 2 && o[0] <= o[1])
+            .ok_or_else(|| invalid(name, "data_offsets"))?;
+
+        let size: usize = match dtype {
+            "F32" => 4,
+            "F64" => 8,
+            _ => {
+                return Err(SafetensorsError::DataType {
+                    name: name.clone(),
+                    dtype: dtype.to_string(),
+                })
+            }
+        };
+        let data_len = shape
+            .iter()
+            .try_fold(size, |n, &dim| n.checked_mul(dim))
+            .ok_or_else(|| invalid(name, "shape"))?;
+        if offsets[1] - offsets[0] != data_len {
+            return Err(invalid(name, "data_offsets"));
+        }
+        let end = offsets[1]
+            .checked_add(8 + len)
+            .ok_or_else(|| invalid(name, "data_offsets"))?;
+        let raw = data
+            .get(offsets[0]..offsets[1])
+            .ok_or(SafetensorsError::Truncated {
+                expected: end,
+                found: bytes.len(),
+            })?;
+
+        let values = raw.chunks(size).map(|b| match size {
+            4 => f32::from_le_bytes(b.try_into().unwrap()),
+            _ => f64::from_le_bytes(b.try_into().unwrap()) as f32,
+        });
+        let (rows, cols) = match shape.len() {
+            0 => (1, 1),
+            1 => (1, shape[0]),
+            _ => (shape[0], shape[1..].iter().product()),
+        };
+        let matrix = Matrix::from_iter(rows, cols, values.collect::<Vec<_>>());
+        tensors.push((name.clone(), shape, matrix));
+    }
+    Ok((tensors, metadata))
+}
+
+/// Layer weights are stored as `[outputs, inputs]` like the weight of a
+/// PyTorch `nn.Linear`, i.e. transposed from the `x * W` layout used here.
+fn is_linear_weight(name: &str) -> bool {
+    name.ends_with(".weight") && !name.contains(".norm.")
+}
+
+/// The safetensors shape of a network tensor: layer weights are
+/// `[outputs, inputs]`, biases and normalization parameters are vectors.
+pub(crate) fn shape(name: &str, tensor: &Matrix) -> Vec<usize> {
+    match is_linear_weight(name) {
+        true => vec![tensor.cols(), tensor.rows()],
+        false => vec![tensor.len()],
+    }
+}
+
+/// A network tensor with its shape and data in the layout of the file.
+pub(crate) fn store(name: String, tensor: &Matrix) -> (String, Vec<usize>, Matrix) {
+    let shape = shape(&name, tensor);
+    let tensor = match is_linear_weight(&name) {
+        true => tensor.transpose(),
+        false => tensor.clone(),
+    };
+    (name, shape, tensor)
+}
+
+/// Undoes [`store`] for a tensor read with the shape it expects.
+pub(crate) fn restore(name: &str, tensor: Matrix) -> Matrix {
+    match is_linear_weight(name) {
+        true => tensor.transpose(),
+        false => tensor,
+    }
+}
+
+impl NeuralNetwork {
+    /// Serializes every tensor named by [`NeuralNetwork::tensors`] in the
+    /// safetensors format, recording the layer sizes and activations in the
+    /// metadata. Layer weights are written as `[outputs, inputs]`, the
+    /// layout of a PyTorch `nn.Linear` weight.
+    pub fn to_safetensors(&self) -> Vec<u8> {
+        let tensors = self
+            .tensors()
+            .into_iter()
+            .map(|(name, tensor)| store(name, tensor))
+            .collect::<Vec<_>>();
+        let sizes = Json::from(self.sizes()).to_string();
+        let activations = self.activations().iter().map(|a| a.to_string());
+        let activations = Json::from(activations.collect::<Vec<_>>()).to_string();
+        serialize(
+            &tensors,
+            &[
+                ("format".to_string(), "rustml".to_string()),
+                ("sizes".to_string(), sizes),
+                ("activations".to_string(), activations),
+                ("loss".to_string(), self.loss().to_string()),
+            ],
+        )
+    }
+
+    /// Builds a network from a file written by
+    /// [`NeuralNetwork::to_safetensors`], taking the layer sizes and
+    /// activations, and the loss if present, from the metadata. Layers with `norm.running_mean` get a
+    /// batch norm, other layers with `norm.weight` a layer norm.
+    pub fn from_safetensors(bytes: &[u8]) -> Result<Self, SafetensorsError> {
+        let (tensors, metadata) = deserialize(bytes)?;
+        let json = |key: &str| -> Result<Json, SafetensorsError> {
+            let value = metadata
+                .iter()
+                .find(|(k, _)| k == key)
+                .ok_or_else(|| SafetensorsError::Header(format!("missing `{key}` metadata")))?;
+            Ok(Json::parse(&value.1)?)
+        };
+        let invalid = |key: &str| SafetensorsError::Header(format!("invalid `{key}` metadata"));
+
+        let sizes = json("sizes")?;
+        let sizes = sizes
+            .as_array()
+            .and_then(|sizes| sizes.iter().map(Json::as_usize).collect::<Option<Vec<_>>>())
+            .filter(|sizes| sizes.len() > 1)
+            .ok_or_else(|| invalid("sizes"))?;
+        let activations = json("activations")?;
+        let activations = activations
+            .as_array()
+            .and_then(|a| a.iter().map(|a| a.as_str()?.parse().ok()).collect())
+            .filter(|a: &Vec<Activation>| a.len() == sizes.len() - 1)
+            .ok_or_else(|| invalid("activations"))?;
+
+        let mut nn = NeuralNetwork::new(&sizes);
+        if let Some((_, loss)) = metadata.iter().find(|(key, _)| key == "loss") {
+            nn.set_loss(loss.parse().map_err(|_| invalid("loss"))?);
+        }
+        for (i, activation) in activations.into_iter().enumerate() {
+            nn.set_activation(i, activation);
+            let has = |name: &str| {
+                let name = format!("layers.{i}.norm.{name}");
+                tensors.iter().any(|(n, _, _)| *n == name)
+            };
+            if has("running_mean") {
+                nn.set_norm(i, Norm::Batch(BatchNorm1d::new(sizes[i + 1])));
+            } else if has("weight") {
+                nn.set_norm(i, Norm::Layer(LayerNorm::new(sizes[i + 1])));
+            }
+        }
+        nn.load_tensors(tensors)?;
+        Ok(nn)
+    }
+
+    pub fn write_safetensors<P: AsRef<Path>>(&self, path: P) -> Result<(), SafetensorsError> {
+        Ok(std::fs::write(path, self.to_safetensors())?)
+    }
+
+    /// Loads the tensors of a safetensors file into `self`. Every tensor of
+    /// the architecture must be present with the same shape and no others;
+    /// on error `self` is left unchanged.
+    pub fn load_safetensors(&mut self, bytes: &[u8]) -> Result<(), SafetensorsError> {
+        self.load_tensors(deserialize(bytes)?.0)
+    }
+
+    pub(crate) fn load_tensors(
+        &mut self,
+        mut loaded: Vec<(String, Vec<usize>, Matrix)>,
+    ) -> Result<(), SafetensorsError> {
+        let mut values = Vec::new();
+        for (name, tensor) in self.tensors() {
+            let i = loaded
+                .iter()
+                .position(|(n, _, _)| *n == name)
+                .ok_or_else(|| SafetensorsError::Missing(name.clone()))?;
+            let (name, found, value) = loaded.swap_remove(i);
+            let expected = shape(&name, tensor);
+            if found != expected {
+                return Err(SafetensorsError::Shape {
+                    name,
+                    expected,
+                    found,
+                });
+            }
+            values.push(restore(&name, value));
+        }
+        if let Some((name, _, _)) = loaded.into_iter().next() {
+            return Err(SafetensorsError::Unexpected(name));
+        }
+
+        for ((_, tensor), value) in self.tensors_mut().into_iter().zip(values) {
+            *tensor = value;
+        }
+        Ok(())
+    }
+
+    pub fn read_safetensors<P: AsRef<Path>>(&mut self, path: P) -> Result<(), SafetensorsError> {
+        self.load_safetensors(&std::fs::read(path)?)
+    }
+}
+
+fn take(bytes: &[u8], start: usize, len: usize) -> Result<&[u8], SafetensorsError> {
+    bytes
+        .get(start..start.saturating_add(len))
+        .ok_or(SafetensorsError::Truncated {
+            expected: start.saturating_add(len),
+            found: bytes.len(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::Init;
+    use crate::loss::Loss;
+
+    #[test]
+    fn test_round_trip() {
+        let mut nn = NeuralNetwork::with_init(&[3, 4, 2], Init::XavierNormal, Init::HeUniform, 3);
+        nn.set_norm(0, Norm::Batch(BatchNorm1d::new(4)));
+        nn.backprop(
+            &mut nn.clone(),
+            &Matrix::from_iter(2, 3, (0..6).map(|x| x as f32)),
+            &Matrix::from_iter(2, 2, vec![0.0, 1.0, 1.0, 0.0]),
+        );
+
+        let bytes = nn.to_safetensors();
+        let header_len = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+        assert_eq!(header_len % 8, 0);
+
+        let mut loaded = NeuralNetwork::new(&[3, 4, 2]);
+        loaded.set_norm(0, Norm::Batch(BatchNorm1d::new(4)));
+        loaded.load_safetensors(&bytes).unwrap();
+        assert_eq!(loaded.tensors(), nn.tensors());
+    }
+
+    #[test]
+    fn test_from_safetensors() {
+        let mut nn = NeuralNetwork::with_init(&[2, 3, 3, 1], Init::HeNormal, Init::HeNormal, 4);
+        nn.set_norm(0, Norm::Batch(BatchNorm1d::new(3)));
+        nn.set_norm(1, Norm::Layer(LayerNorm::new(3)));
+        nn.set_activation(1, Activation::Tanh);
+        nn.set_loss(Loss::CrossEntropy);
+
+        let loaded = NeuralNetwork::from_safetensors(&nn.to_safetensors()).unwrap();
+        assert_eq!(loaded.sizes(), vec![2, 3, 3, 1]);
+        assert_eq!(loaded.loss(), Loss::CrossEntropy);
+        assert_eq!(loaded.activations(), nn.activations());
+        assert_eq!(loaded.tensors(), nn.tensors());
+
+        let tensor = Matrix::new(1, 1);
+        let bytes = serialize(&[("x".to_string(), vec![1], &tensor)], &[]);
+        let err = NeuralNetwork::from_safetensors(&bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid safetensors header: missing `sizes` metadata"
+        );
+    }
+
+    #[test]
+    fn test_header() {
+        let nn = NeuralNetwork::from_iter(&[3, 2], (0..).map(|x| x as f32));
+        let (tensors, metadata) = deserialize(&nn.to_safetensors()).unwrap();
+        let shapes = tensors
+            .iter()
+            .map(|(name, shape, _)| (name.as_str(), shape.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shapes,
+            vec![("layers.0.weight", vec![2, 3]), ("layers.0.bias", vec![2])]
+        );
+        // Like `nn.Linear`, row `o` of the stored weight holds the weights
+        // from every input to output `o`.
+        assert_eq!(tensors[0].2, nn.tensors()[0].1.transpose());
+        assert!(metadata.contains(&("sizes".to_string(), "[3,2]".to_string())));
+    }
+
+    #[test]
+    fn test_validation() {
+        let bytes = NeuralNetwork::new(&[2, 3, 1]).to_safetensors();
+
+        let err = NeuralNetwork::new(&[2, 4, 1])
+            .load_safetensors(&bytes)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "tensor `layers.0.weight` has shape [3, 2], expected [4, 2]"
+        );
+
+        let err = NeuralNetwork::new(&[2, 3, 1, 1])
+            .load_safetensors(&bytes)
+            .unwrap_err();
+        assert!(matches!(err, SafetensorsError::Missing(name) if name == "layers.2.weight"));
+
+        let err = NeuralNetwork::new(&[2, 3])
+            .load_safetensors(&bytes)
+            .unwrap_err();
+        assert!(matches!(err, SafetensorsError::Unexpected(_)));
+
+        let tensor = Matrix::new(1, 2);
+        let bytes = serialize(&[("x".to_string(), vec![2], &tensor)], &[]);
+        let bytes = String::from_utf8_lossy(&bytes).replace("F32", "I32");
+        let err = deserialize(bytes.as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "tensor `x` has unsupported dtype I32");
+
+        let header = r#"{"x":{"dtype":"F32","shape":[4294967296,4294967296,4294967296],"data_offsets":[0,0]}}"#;
+        let bytes = [&(header.len() as u64).to_le_bytes(), header.as_bytes()].concat();
+        let err = deserialize(&bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid safetensors header: invalid `shape` for tensor `x`"
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use std::f32::consts::PI;
+
+/// How a [`Scheduler`] changes the learning rate over time, as a function of
+/// the base rate and the step or epoch count `t`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Schedule {
+    /// The base rate throughout, e.g. to only warm up.
+    Constant,
+    /// `rate * gamma^(t / step_size)`, rounding `t / step_size` down.
+    StepDecay { step_size: usize, gamma: f32 },
+    /// `rate * gamma^t`.
+    Exponential { gamma: f32 },
+    /// Cosine annealing from the base rate to `min_rate` over `period`,
+    /// restarting at the base rate with the period multiplied by
+    /// `multiplier` each time (SGDR).
+    CosineWarmRestarts {
+        period: usize,
+        multiplier: usize,
+        min_rate: f32,
+    },
+    /// Cosine annealing from `rate / div_factor` up to the base rate over the
+    /// first `pct_start` of `total`, then down to
+    /// `rate / (div_factor * final_div_factor)`.
+    OneCycle {
+        total: usize,
+        pct_start: f32,
+        div_factor: f32,
+        final_div_factor: f32,
+    },
+    /// Multiplies the rate by `factor`, down to `min_rate`, whenever the
+    /// observed cost has not improved by more than `min_delta` for more than
+    /// `patience` epochs.
+    ReduceOnPlateau {
+        factor: f32,
+        patience: usize,
+        min_delta: f32,
+        min_rate: f32,
+    },
+}
+
+impl Schedule {
+    /// One-cycle over `total` steps or epochs with the usual `pct_start =
+    /// 0.3`, `div_factor = 25` and `final_div_factor = 1e4`.
+    pub fn one_cycle(total: usize) -> Self {
+        Schedule::OneCycle {
+            total,
+            pct_start: 0.3,
+            div_factor: 25.0,
+            final_div_factor: 1e4,
+        }
+    }
+
+    /// Checks the parameters that [`Scheduler::new`] requires.
+    pub(crate) fn check(&self) -> Result<(), &'static str> {
+        let (valid, message) = match *self {
+            Schedule::StepDecay { step_size, .. } => {
+                (step_size > 0, "`step_size` must be positive")
+            }
+            Schedule::CosineWarmRestarts {
+                period, multiplier, ..
+            } => (
+                period > 0 && multiplier > 0,
+                "`period` and `multiplier` must be positive",
+            ),
+            Schedule::OneCycle {
+                total, pct_start, ..
+            } => (
+                total > 0 && (0.0..1.0).contains(&pct_start),
+                "`total` must be positive and `pct_start` in [0, 1)",
+            ),
+            Schedule::ReduceOnPlateau { factor, .. } => {
+                (factor > 0.0 && factor < 1.0, "`factor` must be in (0, 1)")
+            }
+            Schedule::Constant | Schedule::Exponential { .. } => (true, ""),
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(message),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Schedule::Constant => "constant",
+            Schedule::StepDecay { .. } => "step_decay",
+            Schedule::Exponential { .. } => "exponential",
+            Schedule::CosineWarmRestarts { .. } => "cosine_warm_restarts",
+            Schedule::OneCycle { .. } => "one_cycle",
+            Schedule::ReduceOnPlateau { .. } => "reduce_on_plateau",
+        }
+    }
+}
+
+/// Whether `t` counts optimizer steps or completed epochs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interval {
+    Step,
+    Epoch,
+}
+
+/// Sets the learning rate of a [`crate::Trainer`]'s optimizer before every
+/// step, following a [`Schedule`] of the base `rate` and optionally
+/// warming up linearly over the first `warmup` steps or epochs.
+///
+/// Apart from [`Schedule::ReduceOnPlateau`], which the trainer feeds the
+/// mean cost of every epoch through [`Scheduler::observe`], the rate only
+/// depends on the trainer's counters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scheduler {
+    pub(crate) schedule: Schedule,
+    pub(crate) rate: f32,
+    pub(crate) interval: Interval,
+    pub(crate) warmup: usize,
+    // Reduce-on-plateau state.
+    pub(crate) best: f32,
+    pub(crate) wait: usize,
+    pub(crate) scale: f32,
+}
+
+impl Scheduler {
+    /// Updates per epoch; see [`Scheduler::with_interval`].
+    pub fn new(rate: f32, schedule: Schedule) -> Self {
+        if let Err(message) = schedule.check() {
+            panic!("{message}");
+        }
+        Self {
+            schedule,
+            rate,
+            interval: Interval::Epoch,
+            warmup: 0,
+            best: f32::INFINITY,
+            wait: 0,
+            scale: 1.0,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Interval) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Scales the rate by `(t + 1) / warmup` for the first `warmup` steps or
+    /// epochs.
+    pub fn with_warmup(mut self, warmup: usize) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    pub fn interval(&self) -> Interval {
+        self.interval
+    }
+
+    /// The learning rate at step or epoch `t`, counting from 0.
+    pub fn rate(&self, t: usize) -> f32 {
+        let base = self.rate;
+        let rate = match self.schedule {
+            Schedule::Constant => base,
+            Schedule::StepDecay { step_size, gamma } => base * gamma.powi((t / step_size) as i32),
+            Schedule::Exponential { gamma } => base * gamma.powi(t as i32),
+            Schedule::CosineWarmRestarts {
+                period,
+                multiplier,
+                min_rate,
+            } => {
+                let (mut t, mut period) = (t, period);
+                while t >= period {
+                    t -= period;
+                    period *= multiplier;
+                }
+                anneal(base, min_rate, t as f32 / period as f32)
+            }
+            Schedule::OneCycle {
+                total,
+                pct_start,
+                div_factor,
+                final_div_factor,
+            } => {
+                let initial = base / div_factor;
+                let up = ((pct_start * total as f32).round() as usize).max(1);
+                match t < up {
+                    true => anneal(initial, base, t as f32 / up as f32),
+                    false => {
+                        let down = (total - up.min(total)).max(1);
+                        let progress = ((t - up) as f32 / down as f32).min(1.0);
+                        anneal(base, initial / final_div_factor, progress)
+                    }
+                }
+            }
+            Schedule::ReduceOnPlateau { min_rate, .. } => (base * self.scale).max(min_rate),
+        };
+        match t < self.warmup {
+            true => rate * (t + 1) as f32 / self.warmup as f32,
+            false => rate,
+        }
+    }
+
+    /// Records the cost of an epoch. Only [`Schedule::ReduceOnPlateau`]
+    /// uses it.
+    pub fn observe(&mut self, cost: f32) {
+        let Schedule::ReduceOnPlateau {
+            factor,
+            patience,
+            min_delta,
+            ..
+        } = self.schedule
+        else {
+            return;
+        };
+        if cost < self.best - min_delta {
+            self.best = cost;
+            self.wait = 0;
+        } else {
+            self.wait += 1;
+            if self.wait > patience {
+                self.scale *= factor;
+                self.wait = 0;
+            }
+        }
+    }
+}
+
+/// Cosine interpolation from `start` at `progress` 0 to `end` at 1.
+fn anneal(start: f32, end: f32, progress: f32) -> f32 {
+    end + (start - end) * (1.0 + (PI * progress).cos()) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-6, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_schedules() {
+        let step = Scheduler::new(
+            0.1,
+            Schedule::StepDecay {
+                step_size: 2,
+                gamma: 0.5,
+            },
+        );
+        let rates = (0..5).map(|t| step.rate(t)).collect::<Vec<_>>();
+        assert_eq!(rates, vec![0.1, 0.1, 0.05, 0.05, 0.025]);
+
+        let exponential = Scheduler::new(0.1, Schedule::Exponential { gamma: 0.9 });
+        close(exponential.rate(2), 0.081);
+
+        let cosine = Scheduler::new(
+            1.0,
+            Schedule::CosineWarmRestarts {
+                period: 2,
+                multiplier: 2,
+                min_rate: 0.0,
+            },
+        );
+        let rates = (0..7).map(|t| cosine.rate(t)).collect::<Vec<_>>();
+        for (rate, expected) in rates
+            .into_iter()
+            .zip([1.0, 0.5, 1.0, 0.853553, 0.5, 0.146447, 1.0])
+        {
+            close(rate, expected);
+        }
+
+        let one_cycle = Scheduler::new(1.0, Schedule::one_cycle(10));
+        close(one_cycle.rate(0), 0.04);
+        close(one_cycle.rate(3), 1.0);
+        assert!(one_cycle.rate(5) < 1.0 && one_cycle.rate(5) > one_cycle.rate(8));
+        close(one_cycle.rate(10), 0.04 / 1e4);
+
+        let warmup = Scheduler::new(0.2, Schedule::Constant).with_warmup(4);
+        for (t, expected) in [0.05, 0.1, 0.15, 0.2, 0.2, 0.2].into_iter().enumerate() {
+            close(warmup.rate(t), expected);
+        }
+    }
+
+    #[test]
+    fn test_reduce_on_plateau() {
+        let mut plateau = Scheduler::new(
+            1.0,
+            Schedule::ReduceOnPlateau {
+                factor: 0.5,
+                patience: 1,
+                min_delta: 0.01,
+                min_rate: 0.3,
+            },
+        );
+        let mut rates = Vec::new();
+        for cost in [1.0, 0.9, 0.895, 0.9, 0.8, 0.8, 0.8, 0.8, 0.8] {
+            plateau.observe(cost);
+            rates.push(plateau.rate(0));
+        }
+        assert_eq!(rates, vec![1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.3, 0.3, 0.3]);
+    }
+}
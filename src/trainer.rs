@@ -0,0 +1,354 @@
+use crate::anomaly::{check, Anomaly};
+use crate::callback::Callback;
+use crate::clip::Clip;
+use crate::matrix::{random_permutation, Matrix};
+use crate::neural_network::NeuralNetwork;
+use crate::optimizer::Optimizer;
+use crate::scheduler::{Interval, Scheduler};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::ops::ControlFlow;
+
+/// Mini-batch training loop state: the optimizer, the position in the data
+/// and the seed everything random is derived from.
+///
+/// The shuffle order of an epoch and the dropout masks of a step are derived
+/// from `seed` and the epoch and step counters rather than from a running
+/// generator, so a trainer restored from a checkpoint continues exactly
+/// where the original left off.
+#[derive(Clone, Debug)]
+pub struct Trainer {
+    pub(crate) optimizer: Optimizer,
+    pub(crate) batch_size: usize,
+    pub(crate) seed: u64,
+    pub(crate) epoch: usize,
+    pub(crate) batch: usize,
+    pub(crate) step: u64,
+    pub(crate) scheduler: Option<Scheduler>,
+    pub(crate) clip: Option<Clip>,
+    detect_anomalies: bool,
+    pub(crate) order: Vec<usize>,
+    gradient: Option<NeuralNetwork>,
+}
+
+impl Trainer {
+    pub fn new(optimizer: Optimizer, batch_size: usize, seed: u64) -> Self {
+        assert!(batch_size > 0);
+        Self {
+            optimizer,
+            batch_size,
+            seed,
+            epoch: 0,
+            batch: 0,
+            step: 0,
+            scheduler: None,
+            clip: None,
+            detect_anomalies: false,
+            order: Vec::new(),
+            gradient: None,
+        }
+    }
+
+    pub fn optimizer(&self) -> &Optimizer {
+        &self.optimizer
+    }
+
+    pub fn optimizer_mut(&mut self) -> &mut Optimizer {
+        &mut self.optimizer
+    }
+
+    /// Lets `scheduler` set the optimizer's learning rate before every step,
+    /// replacing the rate it was created with.
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    pub fn scheduler(&self) -> Option<&Scheduler> {
+        self.scheduler.as_ref()
+    }
+
+    /// Clips the gradient of every step before the optimizer applies it.
+    pub fn with_clipping(mut self, clip: Clip) -> Self {
+        self.clip = Some(clip);
+        self
+    }
+
+    pub fn clip(&self) -> Option<Clip> {
+        self.clip
+    }
+
+    /// Checks every layer output and parameter gradient of every step for
+    /// NaN and infinite values, failing the step before the update is
+    /// applied. Off by default, as it scans the whole network each step.
+    pub fn with_anomaly_detection(mut self, detect_anomalies: bool) -> Self {
+        self.detect_anomalies = detect_anomalies;
+        self
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The number of completed epochs.
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// The index of the next batch within the current epoch.
+    pub fn batch(&self) -> usize {
+        self.batch
+    }
+
+    /// The number of completed steps over all epochs.
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    /// Trains `nn` on the next mini-batch of `(input, output)` and returns
+    /// its cost before the update. The last batch of an epoch is smaller
+    /// when the batch size doesn't divide the number of rows.
+    ///
+    /// Panics on an anomaly if anomaly detection is on; see
+    /// [`Trainer::try_train_step`].
+    pub fn train_step(&mut self, nn: &mut NeuralNetwork, input: &Matrix, output: &Matrix) -> f32 {
+        self.try_train_step(nn, input, output)
+            .unwrap_or_else(|anomaly| panic!("{anomaly}"))
+    }
+
+    /// Same as `train_step`, but returns the anomaly found by anomaly
+    /// detection instead of panicking. The parameters and batch norm running
+    /// statistics of `nn` and the trainer are then left as they were before
+    /// the step.
+    pub fn try_train_step(
+        &mut self,
+        nn: &mut NeuralNetwork,
+        input: &Matrix,
+        output: &Matrix,
+    ) -> Result<f32, Anomaly> {
+        let n = input.rows();
+        assert!(n > 0);
+        assert_eq!(n, output.rows());
+
+        if self.batch == 0 || self.order.len() != n {
+            let mut rng = StdRng::seed_from_u64(mix(self.seed ^ mix(self.epoch as u64)));
+            self.order = random_permutation(n, &mut rng);
+        }
+        let start = self.batch * self.batch_size;
+        assert!(
+            start < n,
+            "batch {} is past the end of the {n} rows",
+            self.batch
+        );
+        let rows = &self.order[start..(start + self.batch_size).min(n)];
+        let (x, y) = (input.select_rows(rows), output.select_rows(rows));
+
+        nn.reseed(mix(!self.seed ^ mix(self.step)));
+        let gradient = self.gradient.get_or_insert_with(|| nn.clone());
+        // The forward pass of `backprop` updates the batch norm running
+        // statistics, which an anomalous step must not keep.
+        let norms = self.detect_anomalies.then(|| nn.norms());
+        nn.backprop(gradient, &x, &y);
+        let cost = nn.loss().cost(nn.get_output(), &y) + nn.penalty();
+        if let Some(norms) = norms {
+            if let Err(anomaly) = check(self.step, nn, gradient) {
+                nn.restore_norms(norms);
+                return Err(anomaly);
+            }
+        }
+        if let Some(clip) = &self.clip {
+            clip.apply(gradient);
+        }
+        if let Some(scheduler) = &self.scheduler {
+            let t = match scheduler.interval() {
+                Interval::Step => self.step as usize,
+                Interval::Epoch => self.epoch,
+            };
+            self.optimizer.set_rate(scheduler.rate(t));
+        }
+        self.optimizer.step(nn, gradient);
+
+        self.step += 1;
+        self.batch += 1;
+        if self.batch * self.batch_size >= n {
+            self.batch = 0;
+            self.epoch += 1;
+        }
+        Ok(cost)
+    }
+
+    /// Trains on the remaining batches of the current epoch and returns
+    /// their mean cost.
+    pub fn train_epoch(&mut self, nn: &mut NeuralNetwork, input: &Matrix, output: &Matrix) -> f32 {
+        let mut total = 0.0;
+        let mut batches = 0;
+        loop {
+            total += self.train_step(nn, input, output);
+            batches += 1;
+            if self.batch == 0 {
+                let cost = total / batches as f32;
+                self.end_epoch(cost);
+                return cost;
+            }
+        }
+    }
+
+    /// Trains until `epochs` epochs are complete or a callback breaks,
+    /// calling `callbacks` in order after every batch and epoch and when
+    /// training ends. A resumed trainer continues from its current epoch.
+    ///
+    /// Stops at the first anomaly if anomaly detection is on, without
+    /// calling `on_train_end`.
+    pub fn fit(
+        &mut self,
+        nn: &mut NeuralNetwork,
+        input: &Matrix,
+        output: &Matrix,
+        epochs: usize,
+        callbacks: &mut [&mut dyn Callback],
+    ) -> Result<(), Anomaly> {
+        let (mut total, mut batches) = (0.0, 0);
+        while self.epoch < epochs {
+            let cost = self.try_train_step(nn, input, output)?;
+            total += cost;
+            batches += 1;
+            let mut flow = ControlFlow::Continue(());
+            for callback in callbacks.iter_mut() {
+                if callback.on_batch_end(self, nn, cost).is_break() {
+                    flow = ControlFlow::Break(());
+                }
+            }
+            if self.batch == 0 {
+                let cost = total / batches as f32;
+                (total, batches) = (0.0, 0);
+                self.end_epoch(cost);
+                for callback in callbacks.iter_mut() {
+                    if callback.on_epoch_end(self, nn, cost).is_break() {
+                        flow = ControlFlow::Break(());
+                    }
+                }
+            }
+            if flow.is_break() {
+                break;
+            }
+        }
+        for callback in callbacks.iter_mut() {
+            callback.on_train_end(self, nn);
+        }
+        Ok(())
+    }
+
+    fn end_epoch(&mut self, cost: f32) {
+        if let Some(scheduler) = &mut self.scheduler {
+            scheduler.observe(cost);
+        }
+    }
+}
+
+/// The splitmix64 finalizer, to turn counters into unrelated seeds.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::Init;
+    use crate::layer::{BatchNorm1d, Norm};
+
+    #[test]
+    fn test_epochs_and_batches() {
+        let input = Matrix::from_iter(5, 2, (0..10).map(|x| x as f32 / 10.0));
+        let output = Matrix::from_iter(5, 1, (0..5).map(|x| (x % 2) as f32));
+        let mut nn = NeuralNetwork::with_init(&[2, 3, 1], Init::XavierUniform, Init::Zeros, 1);
+        let mut trainer = Trainer::new(Optimizer::sgd(0.5), 2, 7);
+
+        trainer.train_step(&mut nn, &input, &output);
+        assert_eq!(
+            (trainer.epoch(), trainer.batch(), trainer.step()),
+            (0, 1, 1)
+        );
+        trainer.train_epoch(&mut nn, &input, &output);
+        assert_eq!(
+            (trainer.epoch(), trainer.batch(), trainer.step()),
+            (1, 0, 3)
+        );
+        trainer.train_epoch(&mut nn, &input, &output);
+        assert_eq!(
+            (trainer.epoch(), trainer.batch(), trainer.step()),
+            (2, 0, 6)
+        );
+        assert_eq!(trainer.optimizer().updates(), 6);
+    }
+
+    #[test]
+    fn test_clipping() {
+        let input = Matrix::from_iter(2, 2, vec![1.0, 2.0, -1.0, 3.0]);
+        let output = Matrix::from_iter(2, 1, vec![10.0, -10.0]);
+        let nn = NeuralNetwork::with_init(&[2, 3, 1], Init::XavierUniform, Init::Zeros, 1);
+        let distance = |clip: Option<Clip>| {
+            let mut trainer = Trainer::new(Optimizer::sgd(1.0), 2, 0);
+            if let Some(clip) = clip {
+                trainer = trainer.with_clipping(clip);
+            }
+            let mut stepped = nn.clone();
+            trainer.train_step(&mut stepped, &input, &output);
+            let moved = stepped.parameters().into_iter().zip(nn.parameters());
+            moved
+                .flat_map(|((_, a), (_, b))| {
+                    a.iter()
+                        .zip(b.iter())
+                        .map(|(a, b)| a - b)
+                        .collect::<Vec<_>>()
+                })
+                .map(|d| d * d)
+                .sum::<f32>()
+                .sqrt()
+        };
+        assert!(distance(None) > 1.0);
+        assert!((distance(Some(Clip::Norm(0.1))) - 0.1).abs() < 1e-5);
+        assert!(distance(Some(Clip::Value(0.01))) <= 0.01 * 13f32.sqrt() + 1e-6);
+    }
+
+    #[test]
+    fn test_anomaly_detection() {
+        let mut nn = NeuralNetwork::with_init(&[2, 3, 1], Init::XavierUniform, Init::Zeros, 1);
+        nn.set_norm(0, Norm::Batch(BatchNorm1d::new(3)));
+        let input = Matrix::from_iter(2, 2, vec![1.0, 0.5, f32::NAN, 1.0]);
+        let output = Matrix::from_iter(2, 1, vec![0.0, 1.0]);
+
+        let mut trainer = Trainer::new(Optimizer::sgd(0.1), 2, 0).with_anomaly_detection(true);
+        let mut stepped = nn.clone();
+        let anomaly = trainer
+            .try_train_step(&mut stepped, &input, &output)
+            .unwrap_err();
+        assert_eq!(
+            (anomaly.step, anomaly.layer, anomaly.parameter),
+            (0, 0, None)
+        );
+        assert!(anomaly.value.is_nan());
+        assert_eq!(stepped.tensors(), nn.tensors());
+        assert_eq!(trainer.step(), 0);
+
+        let input = Matrix::from_iter(2, 2, vec![1.0, 0.5, 0.25, 1.0]);
+        let output = Matrix::from_iter(2, 1, vec![0.0, f32::INFINITY]);
+        let anomaly = trainer
+            .try_train_step(&mut stepped, &input, &output)
+            .unwrap_err();
+        assert_eq!(anomaly.parameter.as_deref(), Some("weight"));
+        assert!(anomaly
+            .to_string()
+            .starts_with("step 0: the gradient of `layers.0.weight` contains"));
+
+        let mut trainer = Trainer::new(Optimizer::sgd(0.1), 2, 0);
+        let cost = trainer.train_step(&mut stepped, &input, &output);
+        assert!(!cost.is_finite());
+    }
+}
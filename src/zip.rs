@@ -0,0 +1,472 @@
+//! Just enough of the ZIP and DEFLATE formats to read and write `.npz`
+//! archives: stored entries are written, stored and deflated entries are read.
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+/// Writes an archive of uncompressed entries.
+pub(crate) fn write_zip(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in entries {
+        let offset = out.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+
+        // Version 2.0, no flags, stored, 1980-01-01 00:00.
+        let common = |buf: &mut Vec<u8>| {
+            put16(buf, 20);
+            put16(buf, 0);
+            put16(buf, 0);
+            put16(buf, 0);
+            put16(buf, 0x21);
+            put32(buf, crc);
+            put32(buf, size);
+            put32(buf, size);
+            put16(buf, name.len() as u16);
+            put16(buf, 0);
+        };
+
+        put32(&mut out, 0x0403_4b50);
+        common(&mut out);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        put32(&mut central, 0x0201_4b50);
+        put16(&mut central, 20);
+        common(&mut central);
+        put16(&mut central, 0);
+        put16(&mut central, 0);
+        put16(&mut central, 0);
+        put32(&mut central, 0);
+        put32(&mut central, offset);
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    put32(&mut out, 0x0605_4b50);
+    put16(&mut out, 0);
+    put16(&mut out, 0);
+    put16(&mut out, entries.len() as u16);
+    put16(&mut out, entries.len() as u16);
+    put32(&mut out, central.len() as u32);
+    put32(&mut out, offset);
+    put16(&mut out, 0);
+    out
+}
+
+/// Reads every entry of an archive, in central directory order.
+pub(crate) fn read_zip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let truncated = || "truncated zip archive".to_string();
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| get32(bytes, i) == Some(0x0605_4b50))
+        .ok_or("missing end of central directory")?;
+    let count = get16(bytes, end + 10).ok_or_else(truncated)? as usize;
+    let mut pos = get32(bytes, end + 16).ok_or_else(truncated)? as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        // Offsets are taken relative to sub-slices so that corrupt sizes and
+        // offsets can't overflow.
+        let entry = bytes.get(pos..).ok_or_else(truncated)?;
+        if get32(entry, 0) != Some(0x0201_4b50) {
+            return Err("invalid central directory entry".to_string());
+        }
+        let field16 = |at: usize| get16(entry, at).ok_or_else(truncated);
+        let field32 = |at: usize| get32(entry, at).ok_or_else(truncated);
+
+        let method = field16(10)?;
+        let crc = field32(16)?;
+        let mut compressed = field32(20)? as u64;
+        let mut size = field32(24)? as u64;
+        let (name_len, extra_len, comment_len) = (
+            field16(28)? as usize,
+            field16(30)? as usize,
+            field16(32)? as usize,
+        );
+        let mut offset = field32(42)? as u64;
+
+        let name = entry.get(46..46 + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        // Zip64 stores the real values of saturated fields in an extra block.
+        let extra_start = 46 + name_len;
+        let extra = entry
+            .get(extra_start..extra_start + extra_len)
+            .ok_or_else(truncated)?;
+        let mut i = 0;
+        while i + 4 <= extra.len() {
+            let (id, len) = (
+                get16(extra, i).unwrap(),
+                get16(extra, i + 2).unwrap() as usize,
+            );
+            if id == 1 {
+                let mut at = i + 4;
+                for field in [&mut size, &mut compressed, &mut offset] {
+                    if *field == 0xFFFF_FFFF {
+                        *field = get64(extra, at).ok_or_else(truncated)?;
+                        at += 8;
+                    }
+                }
+            }
+            i += 4 + len;
+        }
+        pos += extra_start + extra_len + comment_len;
+
+        let local = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| bytes.get(offset..))
+            .ok_or_else(truncated)?;
+        if get32(local, 0) != Some(0x0403_4b50) {
+            return Err(format!("invalid local header for `{name}`"));
+        }
+        let local_name = get16(local, 26).ok_or_else(truncated)? as usize;
+        let local_extra = get16(local, 28).ok_or_else(truncated)? as usize;
+        let start = 30 + local_name + local_extra;
+        let raw = usize::try_from(compressed)
+            .ok()
+            .and_then(|compressed| local.get(start..start.checked_add(compressed)?))
+            .ok_or_else(truncated)?;
+
+        let data = match method {
+            0 => raw.to_vec(),
+            8 => inflate(raw, usize::try_from(size).unwrap_or(usize::MAX))?,
+            method => {
+                return Err(format!(
+                    "unsupported compression method {method} for `{name}`"
+                ))
+            }
+        };
+        if data.len() as u64 != size || crc32(&data) != crc {
+            return Err(format!("corrupt entry `{name}`"));
+        }
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
+
+fn put16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn get16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(at..at.checked_add(2)?)?.try_into().ok()?,
+    ))
+}
+
+fn get32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(at..at.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+fn get64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(at..at.checked_add(8)?)?.try_into().ok()?,
+    ))
+}
+
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn need(&mut self, n: u32) -> Result<u32, String> {
+        while self.count < n {
+            let byte = *self.data.get(self.pos).ok_or("truncated deflate stream")?;
+            self.buffer |= (byte as u32) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1u64 << n) - 1) as u32;
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+}
+
+/// Canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.need(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Decompresses a raw DEFLATE stream (RFC 1951), failing once the output
+/// would grow past `limit` bytes.
+pub(crate) fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut bits = Bits {
+        data,
+        pos: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+
+    loop {
+        let last = bits.need(1)? == 1;
+        match bits.need(2)? {
+            0 => {
+                bits.buffer = 0;
+                bits.count = 0;
+                let header = data
+                    .get(bits.pos..bits.pos + 4)
+                    .ok_or("truncated deflate stream")?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                if len != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err("invalid stored block length".to_string());
+                }
+                let len = len as usize;
+                if out.len() + len > limit {
+                    return Err(too_long());
+                }
+                let block = data
+                    .get(bits.pos + 4..bits.pos + 4 + len)
+                    .ok_or("truncated deflate stream")?;
+                out.extend_from_slice(block);
+                bits.pos += 4 + len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let lit = Huffman::new(&lengths);
+                let dist = Huffman::new(&[5; 30]);
+                codes(&mut bits, &mut out, limit, &lit, &dist)?;
+            }
+            2 => {
+                let nlen = bits.need(5)? as usize + 257;
+                let ndist = bits.need(5)? as usize + 1;
+                let ncode = bits.need(4)? as usize + 4;
+
+                const ORDER: [usize; 19] = [
+                    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+                ];
+                let mut lengths = [0u8; 19];
+                for &i in ORDER.iter().take(ncode) {
+                    lengths[i] = bits.need(3)? as u8;
+                }
+                let code = Huffman::new(&lengths);
+
+                let mut lengths = vec![0u8; nlen + ndist];
+                let mut i = 0;
+                while i < nlen + ndist {
+                    let symbol = code.decode(&mut bits)?;
+                    let (value, repeat) = match symbol {
+                        0..=15 => (symbol as u8, 1),
+                        16 if i > 0 => (lengths[i - 1], 3 + bits.need(2)? as usize),
+                        17 => (0, 3 + bits.need(3)? as usize),
+                        18 => (0, 11 + bits.need(7)? as usize),
+                        _ => return Err("invalid code lengths".to_string()),
+                    };
+                    if i + repeat > lengths.len() {
+                        return Err("invalid code lengths".to_string());
+                    }
+                    lengths[i..i + repeat].fill(value);
+                    i += repeat;
+                }
+
+                let lit = Huffman::new(&lengths[..nlen]);
+                let dist = Huffman::new(&lengths[nlen..]);
+                codes(&mut bits, &mut out, limit, &lit, &dist)?;
+            }
+            _ => return Err("invalid deflate block type".to_string()),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn too_long() -> String {
+    "inflated data is longer than declared".to_string()
+}
+
+fn codes(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    limit: usize,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), String> {
+    loop {
+        let symbol = lit.decode(bits)? as usize;
+        match symbol {
+            0..=255 if out.len() >= limit => return Err(too_long()),
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let i = symbol - 257;
+                let len = LENGTH_BASE[i] as usize + bits.need(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = dist.decode(bits)? as usize;
+                if d >= 30 {
+                    return Err("invalid distance code".to_string());
+                }
+                let distance = DIST_BASE[d] as usize + bits.need(DIST_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err("distance too far back".to_string());
+                }
+                if out.len() + len > limit {
+                    return Err(too_long());
+                }
+                let start = out.len() - distance;
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+            }
+            _ => return Err("invalid literal/length code".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let entries = vec![
+            ("a.npy".to_string(), vec![1, 2, 3]),
+            ("b.npy".to_string(), vec![]),
+        ];
+        assert_eq!(read_zip(&write_zip(&entries)).unwrap(), entries);
+    }
+
+    #[test]
+    fn test_inflate() {
+        // Raw DEFLATE streams from Python's `zlib.compressobj(9, zlib.DEFLATED, -15)`.
+        let fixed = [
+            0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0xb9, 0x00,
+        ];
+        assert_eq!(inflate(&fixed, 24).unwrap(), b"hello hello hello hello\n");
+
+        let dynamic = [
+            0xdd, 0x8d, 0xdb, 0x09, 0x00, 0x30, 0x10, 0xc2, 0x66, 0x8d, 0xe7, 0xfe, 0x33, 0xd4,
+            0xeb, 0x73, 0x87, 0x7e, 0x88, 0x10, 0x22, 0x22, 0xab, 0x10, 0x68, 0xa6, 0x44, 0xe1,
+            0x24, 0x90, 0x06, 0x5e, 0xdd, 0x06, 0x94, 0xb5, 0xfa, 0x59, 0x3a, 0xd3, 0x40, 0x47,
+            0x6b, 0xe3, 0x22, 0x6d, 0xf4, 0xc9, 0xc1, 0x00,
+        ];
+        let expected = (0..300)
+            .map(|i: usize| b"aaaaaaabbbbccd"[(i * i * 31 + i * 7) % 97 % 14])
+            .collect::<Vec<_>>();
+        assert_eq!(inflate(&dynamic, 300).unwrap(), expected);
+
+        let stored = [0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c'];
+        assert_eq!(inflate(&stored, 3).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_inflate_errors() {
+        let stored = [0x01, 0x03, 0x00, 0xfd, 0xff, b'a', b'b', b'c'];
+        assert_eq!(
+            inflate(&stored, 3).unwrap_err(),
+            "invalid stored block length"
+        );
+
+        let stored = [0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c'];
+        assert_eq!(inflate(&stored, 2).unwrap_err(), too_long());
+
+        let fixed = [
+            0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0xb9, 0x00,
+        ];
+        assert_eq!(inflate(&fixed, 23).unwrap_err(), too_long());
+    }
+
+    #[test]
+    fn test_zip64_offset_overflow() {
+        let mut bytes = write_zip(&[("a.npy".to_string(), vec![1, 2, 3])]);
+        let central = (0..bytes.len())
+            .find(|&i| get32(&bytes, i) == Some(0x0201_4b50))
+            .unwrap();
+        // Saturate the local header offset and give it a Zip64 value of
+        // `u64::MAX`.
+        bytes[central + 30..central + 32].copy_from_slice(&12u16.to_le_bytes());
+        bytes[central + 42..central + 46].copy_from_slice(&[0xFF; 4]);
+        let extra = [&[1, 0, 8, 0][..], &[0xFF; 8]].concat();
+        let at = central + 46 + "a.npy".len();
+        bytes.splice(at..at, extra);
+        assert_eq!(read_zip(&bytes).unwrap_err(), "truncated zip archive");
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn rustml(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rustml"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn temp(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rustml_test_cli_{name}"))
+}
+
+#[test]
+fn test_train_eval_predict() {
+    let data = temp("or.csv");
+    std::fs::write(&data, "a,b,or\n0,0,0\n0,1,1\n1,0,1\n1,1,1\n").unwrap();
+    let model = temp("or.safetensors");
+    let (data, model) = (data.to_str().unwrap(), model.to_str().unwrap());
+
+    let args = [
+        "train",
+        data,
+        "--model",
+        model,
+        "--hidden",
+        "4",
+        "--activation",
+        "tanh",
+        "--loss",
+        "cross_entropy",
+        "--optimizer",
+        "adam",
+        "--rate",
+        "0.1",
+        "--batch-size",
+        "2",
+        "--epochs",
+        "200",
+        "--seed",
+        "3",
+    ];
+    let trained = stdout(&rustml(&args));
+    assert!(trained.contains("epoch: 200 cost: "));
+    assert_eq!(stdout(&rustml(&args)), trained, "training is seeded");
+
+    let evaluated = stdout(&rustml(&["eval", data, "--model", model]));
+    assert!(evaluated.starts_with("cross_entropy: "), "{evaluated}");
+    assert!(evaluated.contains("accuracy: 1.0000"), "{evaluated}");
+
+    let predicted = stdout(&rustml(&["predict", data, "--model", model]));
+    let lines = predicted.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "output_0");
+    let outputs = lines[1..]
+        .iter()
+        .map(|line| line.parse::<f32>().unwrap().round())
+        .collect::<Vec<_>>();
+    assert_eq!(outputs, vec![0.0, 1.0, 1.0, 1.0]);
+
+    std::fs::remove_file(data).unwrap();
+    std::fs::remove_file(model).unwrap();
+}
+
+#[test]
+fn test_errors() {
+    let output = rustml(&["fit", "data.csv"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("error: unknown command `fit`"));
+    assert!(stderr.contains("usage:"));
+
+    let output = rustml(&["train", "data.csv"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("error: missing `--model`"));
+
+    let data = temp("errors.csv");
+    std::fs::write(&data, "a,b\n0,1\n1,0\n").unwrap();
+    let model = temp("errors.safetensors");
+    let (data, model) = (data.to_str().unwrap(), model.to_str().unwrap());
+    let output = rustml(&["train", data, "--model", model, "--hidden", "4,0"]);
+    std::fs::remove_file(data).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("error: invalid `--hidden` size `0`"));
+}
+
+#[test]
+fn test_train_with_config() {
+    let data = temp("and.csv");
+    std::fs::write(&data, "a,b,and\n0,0,0\n0,1,0\n1,0,0\n1,1,1\n").unwrap();
+    let config = temp("and.json");
+    let model = temp("and.safetensors");
+    let (data, config, model) = (
+        data.to_str().unwrap(),
+        config.to_str().unwrap(),
+        model.to_str().unwrap(),
+    );
+
+    std::fs::write(
+        config,
+        r#"{"sizes": [2, 1], "optimizer": {"rate": 0.1}, "epochs": 5"#,
+    )
+    .unwrap();
+    let output = rustml(&["train", data, "--model", model, "--config", config]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 1"), "{stderr}");
+
+    std::fs::write(config, r#"{"sizes": [2, 1], "optimizer": {"rate": -1}}"#).unwrap();
+    let output = rustml(&["train", data, "--model", model, "--config", config]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("`optimizer.rate`: must be a non-negative number"),
+        "{stderr}"
+    );
+
+    std::fs::write(
+        config,
+        r#"{"sizes": [2, 1], "loss": "cross_entropy", "optimizer": {"rate": 0.1}, "epochs": 500}"#,
+    )
+    .unwrap();
+    let trained = stdout(&rustml(&[
+        "train", data, "--model", model, "--config", config,
+    ]));
+    assert!(trained.contains("epoch: 500 cost: "));
+    let evaluated = stdout(&rustml(&["eval", data, "--model", model]));
+    assert!(evaluated.contains("accuracy: 1.0000"), "{evaluated}");
+
+    for path in [data, config, model] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_eval_regression() {
+    let data = temp("sum.csv");
+    std::fs::write(&data, "a,b,sum\n0,0,0\n1,2,3\n2,1,3\n2,2,4\n").unwrap();
+    let model = temp("sum.safetensors");
+    let (data, model) = (data.to_str().unwrap(), model.to_str().unwrap());
+
+    let args = [
+        "train",
+        data,
+        "--model",
+        model,
+        "--output-activation",
+        "identity",
+        "--rate",
+        "0.05",
+        "--epochs",
+        "500",
+    ];
+    stdout(&rustml(&args));
+    let evaluated = stdout(&rustml(&["eval", data, "--model", model]));
+    assert!(evaluated.starts_with("mse: "), "{evaluated}");
+    assert!(!evaluated.contains("accuracy"), "{evaluated}");
+    assert!(evaluated.contains("output_0: mae: "), "{evaluated}");
+    assert!(evaluated.contains(" r2: "), "{evaluated}");
+
+    std::fs::remove_file(data).unwrap();
+    std::fs::remove_file(model).unwrap();
+}
@@ -0,0 +1,96 @@
+use rustml::{Activation, Init, Matrix, NeuralNetwork};
+use std::process::Command;
+
+// Generated by `NeuralNetwork::to_rust_source` for `network()`, see
+// `test_fixture_is_up_to_date`.
+#[allow(clippy::all)]
+mod model {
+    include!("fixtures/model.rs");
+}
+
+fn network() -> NeuralNetwork {
+    let mut nn = NeuralNetwork::with_init(&[3, 4, 3, 2], Init::XavierUniform, Init::HeUniform, 37);
+    nn.set_activation(0, Activation::Relu);
+    nn.set_activation(1, Activation::Tanh);
+    nn
+}
+
+#[test]
+fn test_fixture_is_up_to_date() {
+    let source = network().to_rust_source();
+    if std::env::var_os("RUSTML_BLESS").is_some() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/model.rs");
+        std::fs::write(path, &source).unwrap();
+    }
+    assert_eq!(
+        source,
+        include_str!("fixtures/model.rs"),
+        "run with RUSTML_BLESS=1 to regenerate tests/fixtures/model.rs"
+    );
+}
+
+#[test]
+fn test_generated_predict_matches_network() {
+    let input = Matrix::from_iter(5, 3, (0..15).map(|x| (x as f32 * 0.61).sin() * 2.0));
+    let expected = network().test(&input);
+
+    for row in 0..input.rows() {
+        let x = input.get_row(row).unwrap().copied().collect::<Vec<_>>();
+        let y = model::predict(&x.try_into().unwrap());
+        let expected = expected.get_row(row).unwrap().copied().collect::<Vec<_>>();
+        assert_eq!(y.to_vec(), expected);
+    }
+}
+
+#[test]
+fn test_c_header_compiles() {
+    let dir = std::env::temp_dir().join("rustml_test_codegen_c");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("model.h"), network().to_c_header("model")).unwrap();
+    let main = r#"
+#include <stdio.h>
+#include <stdlib.h>
+#include "model.h"
+
+int main(int argc, char **argv) {
+    float x[MODEL_INPUTS], y[MODEL_OUTPUTS];
+    for (int i = 0; i < MODEL_INPUTS && i + 1 < argc; i++) {
+        x[i] = strtof(argv[i + 1], NULL);
+    }
+    model_predict(x, y);
+    for (int i = 0; i < MODEL_OUTPUTS; i++) {
+        printf("%.9g\n", y[i]);
+    }
+    return 0;
+}
+"#;
+    std::fs::write(dir.join("main.c"), main).unwrap();
+    let exe = dir.join("model");
+
+    let status = Command::new("cc")
+        .args(["-std=c99", "-Wall", "-Werror", "-o"])
+        .arg(&exe)
+        .arg(dir.join("main.c"))
+        .arg("-lm")
+        .status();
+    let Ok(status) = status else {
+        // No C compiler; the declarations are checked by the unit tests.
+        std::fs::remove_dir_all(&dir).unwrap();
+        return;
+    };
+    assert!(status.success());
+
+    let input = Matrix::from_iter(3, 3, (0..9).map(|x| (x as f32 * 0.83).cos() * 2.0));
+    let expected = network().test(&input);
+    for row in 0..input.rows() {
+        let args = input.get_row(row).unwrap().map(|x| format!("{x:.9}"));
+        let output = Command::new(&exe).args(args).output().unwrap();
+        assert!(output.status.success());
+        let y = String::from_utf8(output.stdout).unwrap();
+        let y = y.lines().map(|y| y.parse::<f32>().unwrap());
+        for (a, b) in y.zip(expected.get_row(row).unwrap()) {
+            assert!((a - b).abs() < 1e-5, "{a} != {b}");
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,58 @@
+// Generated by rustml. Do not edit.
+
+pub const INPUTS: usize = 3;
+pub const OUTPUTS: usize = 2;
+
+static W0: [[f32; 4]; 3] = [
+    [-0.9202647, 0.03797257, -0.6840162, 0.60674703],
+    [0.20528352, 0.5372554, 0.30665815, -0.44204766],
+    [0.6442237, -0.54185855, 0.28147054, -0.49769947],
+];
+static B0: [f32; 4] = [-0.6233528, 0.13866997, -0.17129004, 0.3208263];
+
+static W1: [[f32; 3]; 4] = [
+    [0.17305636, -0.025450349, -0.8613686],
+    [0.483379, 0.5003269, 0.17992687],
+    [0.4808545, -0.7339976, 0.38354516],
+    [0.1566906, 0.4588015, 0.7673534],
+];
+static B1: [f32; 3] = [-0.3611135, 0.77838385, 0.018661976];
+
+static W2: [[f32; 2]; 3] = [
+    [0.4816804, -0.8398943],
+    [-0.11022204, 0.07962334],
+    [-1.0954127, -0.5392226],
+];
+static B2: [f32; 2] = [-0.35724807, -1.2032008];
+
+fn relu(x: f32) -> f32 {
+    x.max(0.0)
+}
+
+fn tanh(x: f32) -> f32 {
+    x.tanh()
+}
+
+fn sigmoid(x: f32) -> f32 {
+    if x >= 0.0 {
+        1.0 / (1.0 + (-x).exp())
+    } else {
+        x.exp() / (1.0 + x.exp())
+    }
+}
+
+pub fn predict(x: &[f32; INPUTS]) -> [f32; OUTPUTS] {
+    let mut l0 = [0.0f32; 4];
+    l0[0] = relu(x[0] * W0[0][0] + x[1] * W0[1][0] + x[2] * W0[2][0] + B0[0]);
+    l0[1] = relu(x[0] * W0[0][1] + x[1] * W0[1][1] + x[2] * W0[2][1] + B0[1]);
+    l0[2] = relu(x[0] * W0[0][2] + x[1] * W0[1][2] + x[2] * W0[2][2] + B0[2]);
+    l0[3] = relu(x[0] * W0[0][3] + x[1] * W0[1][3] + x[2] * W0[2][3] + B0[3]);
+    let mut l1 = [0.0f32; 3];
+    l1[0] = tanh(l0[0] * W1[0][0] + l0[1] * W1[1][0] + l0[2] * W1[2][0] + l0[3] * W1[3][0] + B1[0]);
+    l1[1] = tanh(l0[0] * W1[0][1] + l0[1] * W1[1][1] + l0[2] * W1[2][1] + l0[3] * W1[3][1] + B1[1]);
+    l1[2] = tanh(l0[0] * W1[0][2] + l0[1] * W1[1][2] + l0[2] * W1[2][2] + l0[3] * W1[3][2] + B1[2]);
+    let mut l2 = [0.0f32; 2];
+    l2[0] = sigmoid(l1[0] * W2[0][0] + l1[1] * W2[1][0] + l1[2] * W2[2][0] + B2[0]);
+    l2[1] = sigmoid(l1[0] * W2[0][1] + l1[1] * W2[1][1] + l1[2] * W2[2][1] + B2[1]);
+    l2
+}
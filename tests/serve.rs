@@ -0,0 +1,144 @@
+use rustml::{Activation, Init, Json, Matrix, NeuralNetwork};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+/// A running `serve` process, killed on drop.
+struct Server {
+    child: Child,
+    addr: String,
+}
+
+impl Server {
+    fn start(name: &str, nn: &NeuralNetwork) -> Self {
+        let path = std::env::temp_dir().join(format!("rustml_test_serve_{name}.safetensors"));
+        nn.write_safetensors(&path).unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_serve"))
+            .arg(&path)
+            .arg("127.0.0.1:0")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let addr = line
+            .trim()
+            .strip_prefix("listening on ")
+            .unwrap()
+            .to_string();
+        Self { child, addr }
+    }
+
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, Json) {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, Json::parse(body).unwrap())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+fn network() -> NeuralNetwork {
+    let mut nn = NeuralNetwork::with_init(&[3, 4, 2], Init::XavierUniform, Init::HeUniform, 5);
+    nn.set_activation(0, Activation::Tanh);
+    nn
+}
+
+#[test]
+fn test_health_and_metadata() {
+    let server = Server::start("metadata", &network());
+
+    let (status, body) = server.request("GET", "/health", "");
+    assert_eq!(status, 200);
+    assert_eq!(body.get("status").and_then(Json::as_str), Some("ok"));
+
+    let (status, body) = server.request("GET", "/metadata", "");
+    assert_eq!(status, 200);
+    assert_eq!(
+        body.to_string(),
+        r#"{"sizes":[3,4,2],"activations":["tanh","sigmoid"]}"#
+    );
+}
+
+#[test]
+fn test_predict() {
+    let mut nn = network();
+    let server = Server::start("predict", &nn);
+
+    let body = r#"{"rows": [[0.5, -1, 2], [0, 0.25, 1e-1]]}"#;
+    let (status, body) = server.request("POST", "/predict", body);
+    assert_eq!(status, 200);
+
+    let input = Matrix::from_iter(2, 3, vec![0.5, -1.0, 2.0, 0.0, 0.25, 0.1]);
+    let expected = nn.test(&input);
+    let rows = body.get("rows").and_then(Json::as_array).unwrap();
+    assert_eq!(rows.len(), 2);
+    for (row, values) in rows.iter().enumerate() {
+        let values = values.as_array().unwrap();
+        for (col, value) in values.iter().enumerate() {
+            let value = value.as_f64().unwrap() as f32;
+            assert_eq!(value, *expected.get(row, col).unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_errors() {
+    let server = Server::start("errors", &network());
+
+    let (status, body) = server.request("POST", "/predict", r#"{"rows": [[1, 2]]}"#);
+    assert_eq!(status, 400);
+    assert_eq!(
+        body.get("error").and_then(Json::as_str),
+        Some("row 0 has 2 values, expected 3")
+    );
+
+    let (status, _) = server.request("POST", "/predict", "{");
+    assert_eq!(status, 400);
+    let (status, _) = server.request("GET", "/predict", "");
+    assert_eq!(status, 405);
+    let (status, _) = server.request("GET", "/missing", "");
+    assert_eq!(status, 404);
+
+    // Neither an oversized nor a deeply nested body takes the server down.
+    let mut stream = TcpStream::connect(&server.addr).unwrap();
+    write!(
+        stream,
+        "POST /predict HTTP/1.1\r\nContent-Length: 1000000000000\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 413 "), "{response}");
+
+    let body = format!("{{\"rows\":{}", "[".repeat(200_000));
+    let (status, body) = server.request("POST", "/predict", &body);
+    assert_eq!(status, 400);
+    assert!(body
+        .get("error")
+        .and_then(Json::as_str)
+        .unwrap()
+        .starts_with("nested deeper than"));
+    let (status, _) = server.request("GET", "/health", "");
+    assert_eq!(status, 200);
+}